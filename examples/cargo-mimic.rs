@@ -1,4 +1,8 @@
-use cargo_options::{Build, Check, Clippy, Doc, Install, Metadata, Run, Rustc, Test};
+use cargo_options::{
+    Add, Build, Check, Clean, Clippy, Config, Doc, Fetch, GenerateLockfile, Info, Init, Install,
+    LocateProject, Login, Logout, Metadata, Miri, New, Nextest, Owner, Pkgid, Remove, Report, Run,
+    Rustc, Rustdoc, Search, Test, Uninstall, Vendor, Yank,
+};
 use clap::Parser;
 
 #[derive(Debug, Parser)]
@@ -8,24 +12,68 @@ use clap::Parser;
     styles = cargo_options::styles(),
 )]
 enum Opt {
+    #[command(name = "add")]
+    Add(Add),
     #[command(name = "build", aliases = &["b"] )]
     Build(Build),
+    #[command(name = "clean")]
+    Clean(Clean),
     #[command(name = "clippy")]
     Clippy(Clippy),
     #[command(name = "check", aliases = &["c"])]
     Check(Check),
+    #[command(name = "config")]
+    Config(Config),
     #[command(name = "doc")]
     Doc(Doc),
+    #[command(name = "fetch")]
+    Fetch(Fetch),
+    #[command(name = "generate-lockfile")]
+    GenerateLockfile(GenerateLockfile),
+    #[command(name = "info")]
+    Info(Info),
+    #[command(name = "init")]
+    Init(Init),
     #[command(name = "install")]
     Install(Install),
+    #[command(name = "locate-project")]
+    LocateProject(LocateProject),
+    #[command(name = "login")]
+    Login(Login),
+    #[command(name = "logout")]
+    Logout(Logout),
     #[command(name = "metadata")]
     Metadata(Metadata),
+    #[command(name = "miri")]
+    Miri(Miri),
+    #[command(name = "new")]
+    New(New),
+    #[command(name = "nextest")]
+    Nextest(Nextest),
+    #[command(name = "owner")]
+    Owner(Owner),
+    #[command(name = "pkgid")]
+    Pkgid(Pkgid),
+    #[command(name = "remove")]
+    Remove(Remove),
+    #[command(name = "report")]
+    Report(Report),
     #[command(name = "rustc")]
     Rustc(Rustc),
     #[command(name = "run", alias = "r")]
     Run(Run),
+    #[command(name = "rustdoc")]
+    Rustdoc(Rustdoc),
+    #[command(name = "search")]
+    Search(Search),
     #[command(name = "test", alias = "t")]
     Test(Test),
+    #[command(name = "uninstall")]
+    Uninstall(Uninstall),
+    #[command(name = "vendor")]
+    Vendor(Vendor),
+    #[command(name = "yank")]
+    Yank(Yank),
 }
 
 fn main() {
@@ -0,0 +1,416 @@
+use std::fmt;
+use std::io;
+use std::ops::{Deref, DerefMut};
+use std::path::PathBuf;
+use std::process::Command;
+use std::str::FromStr;
+
+use clap::{ArgAction, Parser};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::common::{CommonOptions, GlobalOptions};
+use crate::heading;
+
+/// Add dependencies to a Cargo.toml manifest file
+#[derive(Clone, Debug, Default, Parser)]
+#[command(
+    display_order = 1,
+    after_help = "Run `cargo help add` for more detailed information."
+)]
+#[group(skip)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct Add {
+    #[command(flatten)]
+    #[cfg_attr(feature = "serde", serde(flatten))]
+    pub global_options: GlobalOptions,
+
+    /// Disable the default features
+    #[arg(long, conflicts_with = "default_features")]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub no_default_features: bool,
+
+    /// Re-enable the default features
+    #[arg(long)]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub default_features: bool,
+
+    /// Space or comma separated list of features to activate
+    #[arg(short = 'F', long, action = ArgAction::Append)]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub features: Vec<String>,
+
+    /// Mark the dependency as optional
+    #[arg(long, conflicts_with = "no_optional")]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub optional: bool,
+
+    /// Mark the dependency as required
+    #[arg(long)]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub no_optional: bool,
+
+    /// Rename the dependency
+    #[arg(long, value_name = "NAME")]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub rename: Option<String>,
+
+    /// Don't actually write the manifest
+    #[arg(short = 'n', long)]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub dry_run: bool,
+
+    /// Package to modify
+    #[arg(
+        short = 'p',
+        long,
+        value_name = "SPEC",
+        num_args=0..=1,
+        help_heading = heading::PACKAGE_SELECTION,
+    )]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub package: Option<String>,
+
+    /// Filesystem path to local crate to add
+    #[arg(
+        long,
+        value_name = "PATH",
+        conflicts_with_all = ["git", "registry"],
+        help_heading = heading::SOURCE,
+    )]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub path: Option<PathBuf>,
+
+    /// Git repository location
+    #[arg(
+        long,
+        value_name = "URI",
+        conflicts_with_all = ["path", "registry"],
+        help_heading = heading::SOURCE,
+    )]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub git: Option<String>,
+
+    /// Git branch to download the crate from
+    #[arg(
+        long,
+        value_name = "BRANCH",
+        requires = "git",
+        conflicts_with_all = ["tag", "rev"],
+        help_heading = heading::SOURCE,
+    )]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub branch: Option<String>,
+
+    /// Git tag to download the crate from
+    #[arg(
+        long,
+        value_name = "TAG",
+        requires = "git",
+        conflicts_with = "rev",
+        help_heading = heading::SOURCE,
+    )]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub tag: Option<String>,
+
+    /// Git reference to download the crate from
+    #[arg(long, value_name = "REV", requires = "git", help_heading = heading::SOURCE)]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub rev: Option<String>,
+
+    /// Package registry for this dependency
+    #[arg(
+        long,
+        value_name = "NAME",
+        conflicts_with_all = ["git", "path"],
+        help_heading = heading::SOURCE,
+    )]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub registry: Option<String>,
+
+    /// Add as development dependency
+    #[arg(long, conflicts_with = "build", help_heading = heading::SECTION)]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub dev: bool,
+
+    /// Add as build dependency
+    #[arg(long, help_heading = heading::SECTION)]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub build: bool,
+
+    /// Add as dependency to the given target platform
+    #[arg(long, value_name = "TARGET", help_heading = heading::SECTION)]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub target: Option<String>,
+
+    /// Path to Cargo.toml
+    #[arg(long, value_name = "PATH", help_heading = heading::MANIFEST_OPTIONS)]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub manifest_path: Option<PathBuf>,
+
+    /// Ignore `rust-version` specification in packages
+    #[arg(long, help_heading = heading::MANIFEST_OPTIONS)]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub ignore_rust_version: bool,
+
+    /// Reference to a package to add as a dependency
+    #[arg(
+        value_name = "DEP_ID",
+        action = ArgAction::Append,
+        num_args = 0..,
+        required_unless_present_any = ["path", "git"],
+    )]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub dependencies: Vec<DependencySpec>,
+}
+
+impl Add {
+    /// Build a `cargo add` command
+    pub fn command(&self) -> Command {
+        let mut cmd = CommonOptions::cargo_command();
+        self.global_options.apply_top_level(&mut cmd);
+        cmd.arg("add");
+        self.global_options.apply(&mut cmd);
+        if self.no_default_features {
+            cmd.arg("--no-default-features");
+        }
+        if self.default_features {
+            cmd.arg("--default-features");
+        }
+        for feature in &self.features {
+            cmd.arg("--features").arg(feature);
+        }
+        if self.optional {
+            cmd.arg("--optional");
+        }
+        if self.no_optional {
+            cmd.arg("--no-optional");
+        }
+        if let Some(rename) = self.rename.as_ref() {
+            cmd.arg("--rename").arg(rename);
+        }
+        if self.dry_run {
+            cmd.arg("--dry-run");
+        }
+        if let Some(package) = self.package.as_ref() {
+            cmd.arg("--package").arg(package);
+        }
+        if let Some(path) = self.path.as_ref() {
+            cmd.arg("--path").arg(path);
+        }
+        if let Some(git) = self.git.as_ref() {
+            cmd.arg("--git").arg(git);
+        }
+        if let Some(branch) = self.branch.as_ref() {
+            cmd.arg("--branch").arg(branch);
+        }
+        if let Some(tag) = self.tag.as_ref() {
+            cmd.arg("--tag").arg(tag);
+        }
+        if let Some(rev) = self.rev.as_ref() {
+            cmd.arg("--rev").arg(rev);
+        }
+        if let Some(registry) = self.registry.as_ref() {
+            cmd.arg("--registry").arg(registry);
+        }
+        if self.dev {
+            cmd.arg("--dev");
+        }
+        if self.build {
+            cmd.arg("--build");
+        }
+        if let Some(target) = self.target.as_ref() {
+            cmd.arg("--target").arg(target);
+        }
+        if let Some(path) = self.manifest_path.as_ref() {
+            cmd.arg("--manifest-path").arg(path);
+        }
+        if self.ignore_rust_version {
+            cmd.arg("--ignore-rust-version");
+        }
+        for dep in &self.dependencies {
+            cmd.args(dep.args());
+        }
+        cmd
+    }
+}
+
+impl Deref for Add {
+    type Target = GlobalOptions;
+
+    fn deref(&self) -> &Self::Target {
+        &self.global_options
+    }
+}
+
+impl DerefMut for Add {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.global_options
+    }
+}
+
+/// A `<name>[@<version-req>] [+<feature>,...]` dependency reference as
+/// accepted by `cargo add`
+///
+/// The features have to be in the same argument, e.g. `"serde +derive"`,
+/// a separate `+derive` argument is rejected in favour of `--features`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct DependencySpec {
+    /// Package name
+    pub name: String,
+    /// Version requirement, e.g. `1` or `=1.0.38`
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub version_req: Option<String>,
+    /// Features to activate on this dependency
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub features: Vec<String>,
+}
+
+impl DependencySpec {
+    /// Reference the latest version of `name`
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            version_req: None,
+            features: Vec::new(),
+        }
+    }
+
+    /// Constrain the dependency to `version_req`
+    pub fn with_version_req(mut self, version_req: impl Into<String>) -> Self {
+        self.version_req = Some(version_req.into());
+        self
+    }
+
+    /// Activate `feature` on the dependency
+    pub fn with_feature(mut self, feature: impl Into<String>) -> Self {
+        self.features.push(feature.into());
+        self
+    }
+
+    /// The `<name>[@<version-req>]` part
+    fn id(&self) -> String {
+        match self.version_req.as_ref() {
+            Some(version_req) => format!("{}@{}", self.name, version_req),
+            None => self.name.clone(),
+        }
+    }
+
+    /// Arguments to pass to `cargo add`, the features follow as `+<features>`
+    pub fn args(&self) -> Vec<String> {
+        let mut args = vec![self.id()];
+        if !self.features.is_empty() {
+            args.push(format!("+{}", self.features.join(",")));
+        }
+        args
+    }
+}
+
+impl FromStr for DependencySpec {
+    type Err = io::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = |msg: String| io::Error::new(io::ErrorKind::InvalidInput, msg);
+        let mut parts = s.split_whitespace();
+        let id = parts.next().unwrap_or_default();
+        if id.starts_with('+') {
+            return Err(invalid(format!(
+                "`{}` has no dependency to apply to, write it as `<name> {}` in one argument or use `--features`",
+                s.trim(),
+                id
+            )));
+        }
+        let (name, version_req) = match id.split_once('@') {
+            Some((name, version_req)) => (name, Some(version_req)),
+            None => (id, None),
+        };
+        if name.is_empty()
+            || !name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        {
+            return Err(invalid(format!("invalid package name `{}`", name)));
+        }
+        if version_req == Some("") {
+            return Err(invalid(format!("missing version requirement in `{}`", s)));
+        }
+        let mut features = Vec::new();
+        for part in parts {
+            let part = part
+                .strip_prefix('+')
+                .ok_or_else(|| invalid(format!("expected `+<feature>`, found `{}`", part)))?;
+            features.extend(
+                part.split(',')
+                    .filter(|f| !f.is_empty())
+                    .map(ToString::to_string),
+            );
+        }
+        Ok(Self {
+            name: name.to_string(),
+            version_req: version_req.map(ToString::to_string),
+            features,
+        })
+    }
+}
+
+impl fmt::Display for DependencySpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.args().join(" "))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Add, DependencySpec};
+    use clap::{CommandFactory, Parser};
+
+    #[test]
+    fn verify_cli() {
+        <Add as CommandFactory>::command().debug_assert()
+    }
+
+    #[test]
+    fn dependency_spec() {
+        let spec: DependencySpec = "serde@=1.0.38".parse().unwrap();
+        assert_eq!(
+            spec,
+            DependencySpec::new("serde").with_version_req("=1.0.38")
+        );
+        assert_eq!(spec.to_string(), "serde@=1.0.38");
+        assert_eq!(
+            "clap".parse::<DependencySpec>().unwrap().to_string(),
+            "clap"
+        );
+        assert!("@1".parse::<DependencySpec>().is_err());
+        assert!("serde@".parse::<DependencySpec>().is_err());
+        assert!("sérde".parse::<DependencySpec>().is_err());
+        assert!("serde.json".parse::<DependencySpec>().is_err());
+
+        let spec: DependencySpec = "serde@1 +derive,rc".parse().unwrap();
+        assert_eq!(
+            spec,
+            DependencySpec::new("serde")
+                .with_version_req("1")
+                .with_feature("derive")
+                .with_feature("rc")
+        );
+        assert_eq!(spec.args(), ["serde@1", "+derive,rc"]);
+        assert_eq!(
+            "tokio +macros".parse::<DependencySpec>().unwrap(),
+            DependencySpec::new("tokio").with_feature("macros")
+        );
+        assert!("+derive".parse::<DependencySpec>().is_err());
+        assert!("serde derive".parse::<DependencySpec>().is_err());
+    }
+
+    #[test]
+    fn source_conflicts() {
+        assert!(Add::try_parse_from(["add", "--git", "https://x", "--path", "."]).is_err());
+        assert!(Add::try_parse_from(["add", "foo", "--registry", "r", "--path", "."]).is_err());
+        assert!(Add::try_parse_from(["add", "foo", "--branch", "main"]).is_err());
+        let add = Add::try_parse_from(["add", "--git", "https://x", "--tag", "v1", "foo"]).unwrap();
+        assert_eq!(add.dependencies, vec![DependencySpec::new("foo")]);
+    }
+}
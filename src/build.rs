@@ -1,14 +1,18 @@
+use std::io;
 use std::ops::{Deref, DerefMut};
 use std::path::PathBuf;
 use std::process::Command;
 
-use clap::{ArgAction, Parser};
+use clap::Parser;
 
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
-use crate::common::CommonOptions;
+use crate::common::{
+    self, CommonOptions, CompilationOptions, ManifestOptions, PackageSelection, TargetSelection,
+};
 use crate::heading;
+use crate::CargoVersion;
 
 /// Compile a local package and all of its dependencies
 #[derive(Clone, Debug, Default, Parser)]
@@ -23,129 +27,21 @@ pub struct Build {
     #[cfg_attr(feature = "serde", serde(flatten))]
     pub common: CommonOptions,
 
-    /// Path to Cargo.toml
-    #[arg(long, value_name = "PATH", help_heading = heading::MANIFEST_OPTIONS)]
-    #[cfg_attr(feature = "serde", serde(default))]
-    pub manifest_path: Option<PathBuf>,
-
-    /// Build artifacts in release mode, with optimizations
-    #[arg(short = 'r', long, help_heading = heading::COMPILATION_OPTIONS)]
-    #[cfg_attr(feature = "serde", serde(default))]
-    pub release: bool,
-
-    /// Ignore `rust-version` specification in packages
-    #[arg(long)]
-    #[cfg_attr(feature = "serde", serde(default))]
-    pub ignore_rust_version: bool,
-
-    /// Output build graph in JSON (unstable)
-    #[arg(long, help_heading = heading::COMPILATION_OPTIONS)]
-    #[cfg_attr(feature = "serde", serde(default))]
-    pub unit_graph: bool,
-
-    /// Package to build (see `cargo help pkgid`)
-    #[arg(
-        short = 'p',
-        long = "package",
-        value_name = "SPEC",
-        action = ArgAction::Append,
-        num_args=0..=1,
-        help_heading = heading::PACKAGE_SELECTION,
-    )]
-    #[cfg_attr(feature = "serde", serde(default))]
-    pub packages: Vec<String>,
-
-    /// Build all packages in the workspace
-    #[arg(long, help_heading = heading::PACKAGE_SELECTION)]
-    #[cfg_attr(feature = "serde", serde(default))]
-    pub workspace: bool,
-
-    /// Exclude packages from the build
-    #[arg(
-        long,
-        value_name = "SPEC",
-        action = ArgAction::Append,
-        help_heading = heading::PACKAGE_SELECTION,
-    )]
-    #[cfg_attr(feature = "serde", serde(default))]
-    pub exclude: Vec<String>,
-
-    /// Alias for workspace (deprecated)
-    #[arg(long, help_heading = heading::PACKAGE_SELECTION)]
-    #[cfg_attr(feature = "serde", serde(default))]
-    pub all: bool,
-
-    /// Build only this package's library
-    #[arg(long, help_heading = heading::TARGET_SELECTION)]
-    #[cfg_attr(feature = "serde", serde(default))]
-    pub lib: bool,
-
-    /// Build only the specified binary
-    #[arg(
-        long,
-        value_name = "NAME",
-        action = ArgAction::Append,
-        num_args=0..=1,
-        help_heading = heading::TARGET_SELECTION,
-    )]
-    #[cfg_attr(feature = "serde", serde(default))]
-    pub bin: Vec<String>,
-
-    /// Build all binaries
-    #[arg(long, help_heading = heading::TARGET_SELECTION)]
-    #[cfg_attr(feature = "serde", serde(default))]
-    pub bins: bool,
-
-    /// Build only the specified example
-    #[arg(
-        long,
-        value_name = "NAME",
-        action = ArgAction::Append,
-        num_args=0..=1,
-        help_heading = heading::TARGET_SELECTION,
-    )]
-    #[cfg_attr(feature = "serde", serde(default))]
-    pub example: Vec<String>,
-
-    /// Build all examples
-    #[arg(long, help_heading = heading::TARGET_SELECTION)]
-    #[cfg_attr(feature = "serde", serde(default))]
-    pub examples: bool,
-
-    /// Build only the specified test target
-    #[arg(
-        long,
-        value_name = "NAME",
-        action = ArgAction::Append,
-        help_heading = heading::TARGET_SELECTION,
-    )]
-    #[cfg_attr(feature = "serde", serde(default))]
-    pub test: Vec<String>,
+    #[command(flatten)]
+    #[cfg_attr(feature = "serde", serde(flatten))]
+    pub compilation_options: CompilationOptions,
 
-    /// Build all tests
-    #[arg(long, help_heading = heading::TARGET_SELECTION)]
-    #[cfg_attr(feature = "serde", serde(default))]
-    pub tests: bool,
-
-    /// Build only the specified bench target
-    #[arg(
-        long,
-        value_name = "NAME",
-        action = ArgAction::Append,
-        help_heading = heading::TARGET_SELECTION,
-    )]
-    #[cfg_attr(feature = "serde", serde(default))]
-    pub bench: Vec<String>,
+    #[command(flatten)]
+    #[cfg_attr(feature = "serde", serde(flatten))]
+    pub manifest_options: ManifestOptions,
 
-    /// Build all benches
-    #[arg(long, help_heading = heading::TARGET_SELECTION)]
-    #[cfg_attr(feature = "serde", serde(default))]
-    pub benches: bool,
+    #[command(flatten)]
+    #[cfg_attr(feature = "serde", serde(flatten))]
+    pub package_selection: PackageSelection,
 
-    /// Build all targets
-    #[arg(long, help_heading = heading::TARGET_SELECTION)]
-    #[cfg_attr(feature = "serde", serde(default))]
-    pub all_targets: bool,
+    #[command(flatten)]
+    #[cfg_attr(feature = "serde", serde(flatten))]
+    pub target_selection: TargetSelection,
 
     /// Copy final artifacts to this directory (unstable)
     #[arg(long, alias = "out-dir", value_name = "PATH", help_heading = heading::COMPILATION_OPTIONS)]
@@ -167,64 +63,16 @@ impl Build {
     /// Build a `cargo build` command
     pub fn command(&self) -> Command {
         let mut cmd = CommonOptions::cargo_command();
+        self.common.global_options.apply_top_level(&mut cmd);
         cmd.arg("build");
 
-        self.common.apply(&mut cmd);
+        self.common
+            .apply_with_unstable(&mut cmd, &self.unstable_options());
 
-        if let Some(path) = self.manifest_path.as_ref() {
-            cmd.arg("--manifest-path").arg(path);
-        }
-        if self.release {
-            cmd.arg("--release");
-        }
-        if self.ignore_rust_version {
-            cmd.arg("--ignore-rust-version");
-        }
-        if self.unit_graph {
-            cmd.arg("--unit-graph");
-        }
-        for pkg in &self.packages {
-            cmd.arg("--package").arg(pkg);
-        }
-        if self.workspace {
-            cmd.arg("--workspace");
-        }
-        for item in &self.exclude {
-            cmd.arg("--exclude").arg(item);
-        }
-        if self.all {
-            cmd.arg("--all");
-        }
-        if self.lib {
-            cmd.arg("--lib");
-        }
-        for bin in &self.bin {
-            cmd.arg("--bin").arg(bin);
-        }
-        if self.bins {
-            cmd.arg("--bins");
-        }
-        for example in &self.example {
-            cmd.arg("--example").arg(example);
-        }
-        if self.examples {
-            cmd.arg("--examples");
-        }
-        for test in &self.test {
-            cmd.arg("--test").arg(test);
-        }
-        if self.tests {
-            cmd.arg("--tests");
-        }
-        for bench in &self.bench {
-            cmd.arg("--bench").arg(bench);
-        }
-        if self.benches {
-            cmd.arg("--benches");
-        }
-        if self.all_targets {
-            cmd.arg("--all-targets");
-        }
+        self.compilation_options.apply(&mut cmd);
+        self.manifest_options.apply(&mut cmd);
+        self.package_selection.apply(&mut cmd);
+        self.target_selection.apply(&mut cmd);
         if let Some(dir) = self.artifact_dir.as_ref() {
             cmd.arg("--artifact-dir").arg(dir);
         }
@@ -237,6 +85,59 @@ impl Build {
 
         cmd
     }
+
+    /// Build a `cargo build` command that `version` understands
+    ///
+    /// `--artifact-dir` is spelled `--out-dir` before cargo 1.81,
+    /// `-Z unstable-options` is added when an option is unstable on `version`
+    /// and options `version` doesn't have (anymore) are an error.
+    pub fn command_for(&self, version: &CargoVersion) -> io::Result<Command> {
+        let mut build = self.clone();
+        let mut unstable = self.common.check_version(version)?;
+        if self.build_plan {
+            if version.is_at_least(1, 93) {
+                return Err(common::unsupported(
+                    "--build-plan",
+                    "was removed in cargo 1.93",
+                    version,
+                ));
+            }
+            unstable = true;
+        }
+        let artifact_dir = build.artifact_dir.take();
+        unstable |= artifact_dir.is_some();
+        if unstable && !build.no_auto_unstable_options {
+            build.enable_unstable_options();
+        }
+
+        let mut cmd = build.command();
+        if let Some(dir) = artifact_dir {
+            if version.is_at_least(1, 81) {
+                cmd.arg("--artifact-dir").arg(dir);
+            } else {
+                cmd.arg("--out-dir").arg(dir);
+            }
+        }
+        Ok(cmd)
+    }
+
+    /// Unstable options in use, cargo only takes them with `-Z unstable-options`
+    pub fn unstable_options(&self) -> Vec<&'static str> {
+        let mut options = self.common.unstable_options();
+        options.extend(self.compilation_options.unstable_options());
+        if self.artifact_dir.is_some() {
+            options.push("--artifact-dir");
+        }
+        if self.build_plan {
+            options.push("--build-plan");
+        }
+        options
+    }
+
+    /// Whether the options only work on a nightly cargo
+    pub fn requires_nightly(&self) -> bool {
+        self.common.requires_nightly_for(&self.unstable_options())
+    }
 }
 
 impl Deref for Build {
@@ -256,10 +157,114 @@ impl DerefMut for Build {
 #[cfg(test)]
 mod test {
     use super::Build;
-    use clap::CommandFactory;
+    use crate::CargoVersion;
+    use clap::{CommandFactory, Parser};
 
     #[test]
     fn verify_cli() {
         <Build as CommandFactory>::command().debug_assert()
     }
+
+    #[test]
+    fn unstable_options() {
+        let args = |build: &Build| -> Vec<String> {
+            build
+                .command()
+                .get_args()
+                .map(|a| a.to_string_lossy().into_owned())
+                .collect()
+        };
+
+        let build = Build::parse_from(["build", "--release"]);
+        assert!(build.unstable_options().is_empty());
+        assert!(!build.requires_nightly());
+        assert_eq!(args(&build), ["build", "--release"]);
+
+        let mut build = Build::parse_from(["build", "--unit-graph", "--artifact-dir", "out"]);
+        assert_eq!(build.unstable_options(), ["--unit-graph", "--artifact-dir"]);
+        assert!(build.requires_nightly());
+        assert_eq!(
+            args(&build),
+            [
+                "build",
+                "-Z",
+                "unstable-options",
+                "--unit-graph",
+                "--artifact-dir",
+                "out"
+            ]
+        );
+
+        build.unstable_flags.push("unstable-options".to_string());
+        assert_eq!(
+            args(&build)
+                .iter()
+                .filter(|a| *a == "unstable-options")
+                .count(),
+            1
+        );
+
+        build.unstable_flags.clear();
+        build.no_auto_unstable_options = true;
+        assert_eq!(
+            args(&build),
+            ["build", "--unit-graph", "--artifact-dir", "out"]
+        );
+        assert!(build.requires_nightly());
+    }
+
+    #[test]
+    fn command_for_version() {
+        let version = |release: &str| -> CargoVersion {
+            format!("release: {}\nhost: x86_64-unknown-linux-gnu\n", release)
+                .parse()
+                .unwrap()
+        };
+        let args = |build: &Build, release: &str| -> Vec<String> {
+            let cmd = build.command_for(&version(release)).unwrap();
+            cmd.get_args()
+                .map(|a| a.to_string_lossy().into_owned())
+                .collect()
+        };
+
+        let build = Build::parse_from(["build", "--out-dir", "out"]);
+        assert_eq!(
+            args(&build, "1.80.0-nightly"),
+            ["build", "-Z", "unstable-options", "--out-dir", "out"]
+        );
+        assert_eq!(
+            args(&build, "1.95.0-nightly"),
+            ["build", "-Z", "unstable-options", "--artifact-dir", "out"]
+        );
+        // Renamed in cargo 1.81
+        assert_eq!(
+            args(&build, "1.80.1"),
+            ["build", "-Z", "unstable-options", "--out-dir", "out"]
+        );
+        assert_eq!(
+            args(&build, "1.81.0"),
+            ["build", "-Z", "unstable-options", "--artifact-dir", "out"]
+        );
+
+        let build = Build::parse_from(["build", "--keep-going", "-Zunstable-options"]);
+        assert_eq!(
+            args(&build, "1.70.0-nightly"),
+            ["build", "-Z", "unstable-options", "--keep-going"]
+        );
+        assert_eq!(
+            args(&build, "1.80.0"),
+            ["build", "-Z", "unstable-options", "--keep-going"]
+        );
+        let build = Build::parse_from(["build", "--keep-going"]);
+        assert_eq!(args(&build, "1.80.0"), ["build", "--keep-going"]);
+        assert!(build.command_for(&version("1.60.0")).is_err());
+
+        let build = Build::parse_from(["build", "--build-plan"]);
+        assert_eq!(
+            args(&build, "1.85.0-nightly"),
+            ["build", "-Z", "unstable-options", "--build-plan"]
+        );
+        let err = build.command_for(&version("1.93.0-nightly")).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::Unsupported);
+    }
 }
@@ -0,0 +1,337 @@
+use std::io;
+use std::ops::{Deref, DerefMut};
+use std::process::Command;
+use std::str::FromStr;
+
+use clap::Parser;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::common::{self, CommonOptions, GlobalOptions};
+
+/// Rust's package manager
+#[derive(Clone, Debug, Default, Parser)]
+#[command(
+    display_order = 1,
+    after_help = "See 'cargo help <command>' for more information on a specific command."
+)]
+#[group(skip)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct Cargo {
+    /// Print version info and exit
+    #[arg(short = 'V', long)]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub version: bool,
+
+    /// List installed commands
+    #[arg(long)]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub list: bool,
+
+    /// Provide a detailed explanation of a rustc error message
+    #[arg(long, value_name = "CODE")]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub explain: Option<String>,
+
+    #[command(flatten)]
+    #[cfg_attr(feature = "serde", serde(flatten))]
+    pub global_options: GlobalOptions,
+}
+
+impl Cargo {
+    /// Build a top-level `cargo` command
+    ///
+    /// `--lockfile-path` is left to the subcommand structs, cargo doesn't
+    /// accept it before the subcommand.
+    pub fn command(&self) -> Command {
+        let mut cmd = CommonOptions::cargo_command();
+        self.global_options.apply_top_level(&mut cmd);
+        if self.version {
+            cmd.arg("--version");
+        }
+        if self.list {
+            cmd.arg("--list");
+        }
+        if let Some(code) = self.explain.as_ref() {
+            cmd.arg("--explain").arg(code);
+        }
+        GlobalOptions {
+            lockfile_path: None,
+            ..self.global_options.clone()
+        }
+        .apply(&mut cmd);
+        cmd
+    }
+
+    /// Run `cargo --list` and parse the installed commands
+    pub fn installed_commands(&self) -> io::Result<InstalledCommands> {
+        let cargo = Cargo {
+            version: false,
+            list: true,
+            explain: None,
+            ..self.clone()
+        };
+        common::capture_stdout(&mut cargo.command())?.parse()
+    }
+
+    /// Run `cargo -vV` and parse the version info
+    pub fn cargo_version(&self) -> io::Result<CargoVersion> {
+        let mut cargo = Cargo {
+            version: true,
+            list: false,
+            explain: None,
+            ..self.clone()
+        };
+        cargo.global_options.verbose = 1;
+        cargo.global_options.provenance.remove("verbose");
+        common::capture_stdout(&mut cargo.command())?.parse()
+    }
+}
+
+impl Deref for Cargo {
+    type Target = GlobalOptions;
+
+    fn deref(&self) -> &Self::Target {
+        &self.global_options
+    }
+}
+
+impl DerefMut for Cargo {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.global_options
+    }
+}
+
+/// Parsed output of `cargo --list`
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct InstalledCommands {
+    /// Installed commands and aliases, in listing order
+    pub commands: Vec<InstalledCommand>,
+}
+
+/// A command or alias listed by `cargo --list`
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct InstalledCommand {
+    /// Command name, e.g. `build` or `nextest`
+    pub name: String,
+    /// One line description, external subcommands usually have none
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub description: Option<String>,
+    /// What the command expands to when it's an alias, e.g. `build`
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub alias: Option<String>,
+}
+
+impl InstalledCommands {
+    /// Look up a command or alias by name
+    pub fn get(&self, name: &str) -> Option<&InstalledCommand> {
+        self.commands.iter().find(|command| command.name == name)
+    }
+}
+
+impl FromStr for InstalledCommands {
+    type Err = io::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut commands = Vec::new();
+        for line in s.lines() {
+            // Entries are indented, the `Installed Commands:` header isn't
+            if !line.starts_with(char::is_whitespace) {
+                continue;
+            }
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            let (name, description) = match line.split_once(char::is_whitespace) {
+                Some((name, description)) => (name, Some(description.trim_start())),
+                None => (line, None),
+            };
+            let alias = description.and_then(|description| description.strip_prefix("alias: "));
+            commands.push(InstalledCommand {
+                name: name.to_string(),
+                description: description
+                    .filter(|_| alias.is_none())
+                    .map(|description| description.to_string()),
+                alias: alias.map(|alias| alias.to_string()),
+            });
+        }
+        Ok(InstalledCommands { commands })
+    }
+}
+
+/// Parsed output of `cargo -vV`
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct CargoVersion {
+    /// Full release string, e.g. `1.80.0` or `1.82.0-nightly`
+    pub release: String,
+    /// Major version
+    pub major: u64,
+    /// Minor version
+    pub minor: u64,
+    /// Patch version
+    pub patch: u64,
+    /// Pre-release part of the release, e.g. `nightly` or `beta.3`
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub pre: Option<String>,
+    /// Commit cargo was built from, missing for builds outside of git
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub commit_hash: Option<String>,
+    /// Date of that commit
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub commit_date: Option<String>,
+    /// Host target triple
+    pub host: String,
+}
+
+impl CargoVersion {
+    /// Detect the version of the cargo `CommonOptions` commands would run
+    pub fn detect() -> io::Result<Self> {
+        Cargo::default().cargo_version()
+    }
+
+    /// Whether this cargo is `major.minor` or newer, nightlies included
+    pub fn is_at_least(&self, major: u64, minor: u64) -> bool {
+        (self.major, self.minor) >= (major, minor)
+    }
+
+    /// Whether this is a nightly (or locally built dev) cargo
+    pub fn is_nightly(&self) -> bool {
+        matches!(self.pre.as_deref(), Some("nightly" | "dev"))
+    }
+}
+
+impl FromStr for CargoVersion {
+    type Err = io::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut version = CargoVersion::default();
+        for line in s.lines() {
+            let Some((key, value)) = line.split_once(": ") else {
+                continue;
+            };
+            let value = value.trim();
+            match key {
+                "release" => version.release = value.to_string(),
+                "commit-hash" => version.commit_hash = Some(value.to_string()),
+                "commit-date" => version.commit_date = Some(value.to_string()),
+                "host" => version.host = value.to_string(),
+                _ => {}
+            }
+        }
+        if version.release.is_empty() {
+            // `cargo -V` without `-v` only prints `cargo 1.80.0 (...)`
+            version.release = s
+                .lines()
+                .next()
+                .and_then(|line| line.strip_prefix("cargo "))
+                .and_then(|line| line.split_whitespace().next())
+                .ok_or_else(|| invalid_data(format!("unexpected version output `{}`", s.trim())))?
+                .to_string();
+        }
+        let (numbers, pre) = match version.release.split_once('-') {
+            Some((numbers, pre)) => (numbers, Some(pre.to_string())),
+            None => (version.release.as_str(), None),
+        };
+        let mut parts = numbers.split('.').map(|part| part.parse::<u64>());
+        match (parts.next(), parts.next(), parts.next(), parts.next()) {
+            (Some(Ok(major)), Some(Ok(minor)), Some(Ok(patch)), None) => {
+                version.major = major;
+                version.minor = minor;
+                version.patch = patch;
+            }
+            _ => {
+                return Err(invalid_data(format!(
+                    "unexpected cargo release `{}`",
+                    version.release
+                )))
+            }
+        }
+        version.pre = pre;
+        Ok(version)
+    }
+}
+
+fn invalid_data(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Cargo, CargoVersion, InstalledCommands};
+    use clap::{CommandFactory, Parser};
+
+    #[test]
+    fn verify_cli() {
+        <Cargo as CommandFactory>::command().debug_assert()
+    }
+
+    #[test]
+    fn top_level_command() {
+        let cargo = Cargo::parse_from(["cargo", "-C", "foo", "--explain", "E0001", "--offline"]);
+        let args: Vec<_> = cargo.command().get_args().map(|a| a.to_owned()).collect();
+        assert_eq!(
+            args,
+            [
+                "-C",
+                "foo",
+                "--explain",
+                "E0001",
+                "--offline",
+                "-Z",
+                "unstable-options"
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_installed_commands() {
+        let list = "\
+Installed Commands:
+    add                  Add dependencies to a Cargo.toml manifest file
+    b                    alias: build
+    build                Compile a local package and all of its dependencies
+    miri
+";
+        let commands: InstalledCommands = list.parse().unwrap();
+        assert_eq!(commands.commands.len(), 4);
+        let b = commands.get("b").unwrap();
+        assert_eq!(b.alias.as_deref(), Some("build"));
+        assert_eq!(b.description, None);
+        let build = commands.get("build").unwrap();
+        assert_eq!(
+            build.description.as_deref(),
+            Some("Compile a local package and all of its dependencies")
+        );
+        let miri = commands.get("miri").unwrap();
+        assert_eq!((&miri.description, &miri.alias), (&None, &None));
+    }
+
+    #[test]
+    fn parse_cargo_version() {
+        let output = "\
+cargo 1.82.0-nightly (2f738d617 2024-08-13)
+release: 1.82.0-nightly
+commit-hash: 2f738d617c6ead388f899802dd1a7fd66858a691
+commit-date: 2024-08-13
+host: x86_64-unknown-linux-gnu
+libgit2: 1.8.1 (sys:0.19.0 vendored)
+os: Ubuntu 22.4.0 (jammy) [64-bit]
+";
+        let version: CargoVersion = output.parse().unwrap();
+        assert_eq!((version.major, version.minor, version.patch), (1, 82, 0));
+        assert_eq!(version.pre.as_deref(), Some("nightly"));
+        assert!(version.is_nightly());
+        assert_eq!(version.commit_date.as_deref(), Some("2024-08-13"));
+        assert_eq!(version.host, "x86_64-unknown-linux-gnu");
+
+        let version: CargoVersion = "cargo 1.80.1 (376290515 2024-07-16)\n".parse().unwrap();
+        assert_eq!(version.release, "1.80.1");
+        assert!(!version.is_nightly());
+        assert!("rustc 1.80.1".parse::<CargoVersion>().is_err());
+    }
+}
@@ -1,122 +1,28 @@
+use std::io;
 use std::ops::{Deref, DerefMut};
-use std::path::PathBuf;
 use std::process::Command;
 
-use clap::{ArgAction, Parser};
+use clap::Parser;
 
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
-use crate::common::CommonOptions;
-use crate::heading;
+use crate::common::{
+    CommonOptions, CompilationOptions, ManifestOptions, PackageSelection, TargetSelection,
+};
+use crate::CargoVersion;
 
 /// `cargo check` options which are also a subset of `cargo clippy`
 #[derive(Clone, Debug, Default, Parser)]
 #[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
 pub struct CheckOptions {
-    /// Package to build (see `cargo help pkgid`)
-    #[arg(
-        short = 'p',
-        long = "package",
-        value_name = "SPEC",
-        action = ArgAction::Append,
-        num_args=0..=1,
-        help_heading = heading::PACKAGE_SELECTION,
-    )]
-    #[cfg_attr(feature = "serde", serde(default))]
-    pub packages: Vec<String>,
-
-    /// Check all packages in the workspace
-    #[arg(long, help_heading = heading::PACKAGE_SELECTION)]
-    #[cfg_attr(feature = "serde", serde(default))]
-    pub workspace: bool,
-
-    /// Exclude packages from the build
-    #[arg(
-        long,
-        value_name = "SPEC",
-        action = ArgAction::Append,
-        help_heading = heading::PACKAGE_SELECTION,
-    )]
-    #[cfg_attr(feature = "serde", serde(default))]
-    pub exclude: Vec<String>,
-
-    /// Alias for workspace (deprecated)
-    #[arg(long, help_heading = heading::PACKAGE_SELECTION,)]
-    #[cfg_attr(feature = "serde", serde(default))]
-    pub all: bool,
-
-    /// Check only this package's library
-    #[arg(long, help_heading = heading::TARGET_SELECTION)]
-    #[cfg_attr(feature = "serde", serde(default))]
-    pub lib: bool,
-
-    /// Check only the specified binary
-    #[arg(
-        long,
-        value_name = "NAME",
-        action = ArgAction::Append,
-        num_args=0..=1,
-        help_heading = heading::TARGET_SELECTION,
-    )]
-    #[cfg_attr(feature = "serde", serde(default))]
-    pub bin: Vec<String>,
-
-    /// Check all binaries
-    #[arg(long, help_heading = heading::TARGET_SELECTION)]
-    #[cfg_attr(feature = "serde", serde(default))]
-    pub bins: bool,
-
-    /// Check only the specified example
-    #[arg(
-        long,
-        value_name = "NAME",
-        action = ArgAction::Append,
-        num_args=0..=1,
-        help_heading = heading::TARGET_SELECTION,
-    )]
-    #[cfg_attr(feature = "serde", serde(default))]
-    pub example: Vec<String>,
-
-    /// Check all examples
-    #[arg(long, help_heading = heading::TARGET_SELECTION)]
-    #[cfg_attr(feature = "serde", serde(default))]
-    pub examples: bool,
-
-    /// Check only the specified test target
-    #[arg(
-        long,
-        value_name = "NAME",
-        action = ArgAction::Append,
-        help_heading = heading::TARGET_SELECTION,
-    )]
-    #[cfg_attr(feature = "serde", serde(default))]
-    pub test: Vec<String>,
-
-    /// Check all tests
-    #[arg(long, help_heading = heading::TARGET_SELECTION)]
-    #[cfg_attr(feature = "serde", serde(default))]
-    pub tests: bool,
-
-    /// Check only the specified bench target
-    #[arg(
-        long,
-        value_name = "NAME",
-        action = ArgAction::Append,
-        help_heading = heading::TARGET_SELECTION,
-    )]
-    #[cfg_attr(feature = "serde", serde(default))]
-    pub bench: Vec<String>,
-
-    /// Check all benches
-    #[arg(long, help_heading = heading::TARGET_SELECTION)]
-    #[cfg_attr(feature = "serde", serde(default))]
-    pub benches: bool,
+    #[command(flatten)]
+    #[cfg_attr(feature = "serde", serde(flatten))]
+    pub package_selection: PackageSelection,
 
-    /// Check all targets
-    #[arg(long, help_heading = heading::TARGET_SELECTION)]
-    #[cfg_attr(feature = "serde", serde(default))]
-    pub all_targets: bool,
+    #[command(flatten)]
+    #[cfg_attr(feature = "serde", serde(flatten))]
+    pub target_selection: TargetSelection,
 
     /// Outputs a future incompatibility report at the end of the build (unstable)
     #[arg(long)]
@@ -126,48 +32,8 @@ pub struct CheckOptions {
 
 impl CheckOptions {
     pub fn apply(&self, cmd: &mut Command) {
-        for pkg in &self.packages {
-            cmd.arg("--package").arg(pkg);
-        }
-        if self.workspace {
-            cmd.arg("--workspace");
-        }
-        for item in &self.exclude {
-            cmd.arg("--exclude").arg(item);
-        }
-        if self.all {
-            cmd.arg("--all");
-        }
-        if self.lib {
-            cmd.arg("--lib");
-        }
-        for bin in &self.bin {
-            cmd.arg("--bin").arg(bin);
-        }
-        if self.bins {
-            cmd.arg("--bins");
-        }
-        for example in &self.example {
-            cmd.arg("--example").arg(example);
-        }
-        if self.examples {
-            cmd.arg("--examples");
-        }
-        for test in &self.test {
-            cmd.arg("--test").arg(test);
-        }
-        if self.tests {
-            cmd.arg("--tests");
-        }
-        for bench in &self.bench {
-            cmd.arg("--bench").arg(bench);
-        }
-        if self.benches {
-            cmd.arg("--benches");
-        }
-        if self.all_targets {
-            cmd.arg("--all-targets");
-        }
+        self.package_selection.apply(cmd);
+        self.target_selection.apply(cmd);
         if self.future_incompat_report {
             cmd.arg("--future-incompat-report");
         }
@@ -191,51 +57,53 @@ pub struct Check {
     #[cfg_attr(feature = "serde", serde(flatten))]
     pub check: CheckOptions,
 
-    /// Path to Cargo.toml
-    #[arg(long, value_name = "PATH", help_heading = heading::MANIFEST_OPTIONS)]
-    #[cfg_attr(feature = "serde", serde(default))]
-    pub manifest_path: Option<PathBuf>,
-
-    /// Build artifacts in release mode, with optimizations
-    #[arg(short = 'r', long, help_heading = heading::COMPILATION_OPTIONS)]
-    #[cfg_attr(feature = "serde", serde(default))]
-    pub release: bool,
-
-    /// Ignore `rust-version` specification in packages
-    #[arg(long)]
-    #[cfg_attr(feature = "serde", serde(default))]
-    pub ignore_rust_version: bool,
+    #[command(flatten)]
+    #[cfg_attr(feature = "serde", serde(flatten))]
+    pub compilation_options: CompilationOptions,
 
-    /// Output build graph in JSON (unstable)
-    #[arg(long, help_heading = heading::COMPILATION_OPTIONS)]
-    #[cfg_attr(feature = "serde", serde(default))]
-    pub unit_graph: bool,
+    #[command(flatten)]
+    #[cfg_attr(feature = "serde", serde(flatten))]
+    pub manifest_options: ManifestOptions,
 }
 
 impl Check {
     /// Build a `cargo check` command
     pub fn command(&self) -> Command {
         let mut cmd = CommonOptions::cargo_command();
+        self.common.global_options.apply_top_level(&mut cmd);
         cmd.arg("check");
 
-        self.common.apply(&mut cmd);
+        self.common
+            .apply_with_unstable(&mut cmd, &self.unstable_options());
         self.check.apply(&mut cmd);
 
-        if let Some(path) = self.manifest_path.as_ref() {
-            cmd.arg("--manifest-path").arg(path);
-        }
-        if self.release {
-            cmd.arg("--release");
-        }
-        if self.ignore_rust_version {
-            cmd.arg("--ignore-rust-version");
-        }
-        if self.unit_graph {
-            cmd.arg("--unit-graph");
-        }
+        self.compilation_options.apply(&mut cmd);
+        self.manifest_options.apply(&mut cmd);
 
         cmd
     }
+
+    /// Build a `cargo check` command that `version` understands
+    ///
+    /// `-Z unstable-options` is added when an option is unstable on `version`
+    /// and options `version` doesn't have (anymore) are an error.
+    pub fn command_for(&self, version: &CargoVersion) -> io::Result<Command> {
+        let mut check = self.clone();
+        check.common.adapt_to_version(version)?;
+        Ok(check.command())
+    }
+
+    /// Unstable options in use, cargo only takes them with `-Z unstable-options`
+    pub fn unstable_options(&self) -> Vec<&'static str> {
+        let mut options = self.common.unstable_options();
+        options.extend(self.compilation_options.unstable_options());
+        options
+    }
+
+    /// Whether the options only work on a nightly cargo
+    pub fn requires_nightly(&self) -> bool {
+        self.common.requires_nightly_for(&self.unstable_options())
+    }
 }
 
 impl Deref for Check {
@@ -255,10 +123,38 @@ impl DerefMut for Check {
 #[cfg(test)]
 mod test {
     use super::Check;
-    use clap::CommandFactory;
+    use crate::CargoVersion;
+    use clap::{CommandFactory, Parser};
 
     #[test]
     fn verify_cli() {
         <Check as CommandFactory>::command().debug_assert()
     }
+
+    #[test]
+    fn command_for_version() {
+        let version =
+            |release: &str| -> CargoVersion { format!("release: {}\n", release).parse().unwrap() };
+        let args = |check: &Check, release: &str| -> Vec<String> {
+            let cmd = check.command_for(&version(release)).unwrap();
+            cmd.get_args()
+                .map(|a| a.to_string_lossy().into_owned())
+                .collect()
+        };
+
+        let check = Check::parse_from(["check", "--keep-going"]);
+        assert_eq!(
+            args(&check, "1.73.0"),
+            ["check", "-Z", "unstable-options", "--keep-going"]
+        );
+        assert_eq!(args(&check, "1.74.0"), ["check", "--keep-going"]);
+        assert!(check.command_for(&version("1.60.0")).is_err());
+
+        let check = Check::parse_from(["check", "--timings=json"]);
+        assert_eq!(
+            args(&check, "1.93.0-nightly"),
+            ["check", "-Z", "unstable-options", "--timings=json"]
+        );
+        assert!(check.command_for(&version("1.94.0")).is_err());
+    }
 }
@@ -0,0 +1,263 @@
+use std::io;
+use std::ops::{Deref, DerefMut};
+use std::path::PathBuf;
+use std::process::Command;
+use std::str::FromStr;
+
+use clap::{ArgAction, Parser};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::common::{self, CommonOptions, GlobalOptions};
+use crate::heading;
+
+/// Remove artifacts that cargo has generated in the past
+#[derive(Clone, Debug, Default, Parser)]
+#[command(
+    display_order = 1,
+    after_help = "Run `cargo help clean` for more detailed information."
+)]
+#[group(skip)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct Clean {
+    #[command(flatten)]
+    #[cfg_attr(feature = "serde", serde(flatten))]
+    pub global_options: GlobalOptions,
+
+    /// Package to clean artifacts for
+    #[arg(
+        short = 'p',
+        long = "package",
+        value_name = "SPEC",
+        action = ArgAction::Append,
+        num_args=0..=1,
+        help_heading = heading::PACKAGE_SELECTION,
+    )]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub packages: Vec<String>,
+
+    /// Whether or not to clean just the documentation directory
+    #[arg(long)]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub doc: bool,
+
+    /// Whether or not to clean release artifacts
+    #[arg(short = 'r', long, help_heading = heading::COMPILATION_OPTIONS)]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub release: bool,
+
+    /// Clean artifacts of the specified profile
+    #[arg(
+        long,
+        value_name = "PROFILE-NAME",
+        help_heading = heading::COMPILATION_OPTIONS,
+    )]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub profile: Option<String>,
+
+    /// Target triple to clean output for
+    #[arg(
+        long,
+        value_name = "TRIPLE",
+        action = ArgAction::Append,
+        help_heading = heading::COMPILATION_OPTIONS,
+    )]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub target: Vec<String>,
+
+    /// Directory for all generated artifacts
+    #[arg(
+        long,
+        value_name = "DIRECTORY",
+        help_heading = heading::COMPILATION_OPTIONS,
+    )]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub target_dir: Option<PathBuf>,
+
+    /// Display what would be deleted without deleting anything
+    #[arg(short = 'n', long)]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub dry_run: bool,
+
+    /// Path to Cargo.toml
+    #[arg(long, value_name = "PATH", help_heading = heading::MANIFEST_OPTIONS)]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub manifest_path: Option<PathBuf>,
+}
+
+impl Clean {
+    /// Build a `cargo clean` command
+    pub fn command(&self) -> Command {
+        let mut cmd = CommonOptions::cargo_command();
+        self.global_options.apply_top_level(&mut cmd);
+        cmd.arg("clean");
+        self.global_options.apply(&mut cmd);
+        for pkg in &self.packages {
+            cmd.arg("--package").arg(pkg);
+        }
+        if self.doc {
+            cmd.arg("--doc");
+        }
+        if self.release {
+            cmd.arg("--release");
+        }
+        if let Some(profile) = self.profile.as_ref() {
+            cmd.arg("--profile").arg(profile);
+        }
+        for target in &self.target {
+            cmd.arg("--target").arg(common::rust_target(target));
+        }
+        if let Some(dir) = self.target_dir.as_ref() {
+            cmd.arg("--target-dir").arg(dir);
+        }
+        if self.dry_run {
+            cmd.arg("--dry-run");
+        }
+        if let Some(path) = self.manifest_path.as_ref() {
+            cmd.arg("--manifest-path").arg(path);
+        }
+        cmd
+    }
+}
+
+impl Deref for Clean {
+    type Target = GlobalOptions;
+
+    fn deref(&self) -> &Self::Target {
+        &self.global_options
+    }
+}
+
+impl DerefMut for Clean {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.global_options
+    }
+}
+
+/// The `Summary`/`Removed` line `cargo clean` prints on stderr
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct CleanSummary {
+    /// Whether nothing was actually removed because of `--dry-run`
+    pub dry_run: bool,
+    /// Number of files (that would be) removed
+    pub files: u64,
+    /// Number of directories (that would be) removed
+    pub directories: u64,
+    /// Total size of the removed files.
+    ///
+    /// Cargo only prints this rounded to one decimal of its unit,
+    /// so it is approximate for anything larger than a KiB.
+    pub bytes: u64,
+}
+
+impl FromStr for CleanSummary {
+    type Err = io::Error;
+
+    /// Parse the summary out of `cargo clean` stderr
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        for line in s.lines().rev() {
+            let (dry_run, rest) = if let Some((_, rest)) = line.split_once("Summary ") {
+                (true, rest)
+            } else if let Some((_, rest)) = line.split_once("Removed ") {
+                (false, rest)
+            } else {
+                continue;
+            };
+            let mut summary = CleanSummary {
+                dry_run,
+                ..Default::default()
+            };
+            for part in rest.trim().split(", ") {
+                if let Some(size) = part.strip_suffix(" total") {
+                    summary.bytes = parse_size(size)?;
+                } else if let Some((count, unit)) = part.split_once(' ') {
+                    let count = count
+                        .parse()
+                        .map_err(|_| invalid_data(format!("invalid count in `{}`", line)))?;
+                    match unit {
+                        "file" | "files" => summary.files = count,
+                        "directory" | "directories" => summary.directories = count,
+                        _ => return Err(invalid_data(format!("unexpected `{}`", part))),
+                    }
+                } else {
+                    return Err(invalid_data(format!("unexpected `{}`", part)));
+                }
+            }
+            return Ok(summary);
+        }
+        Err(invalid_data("no clean summary found".to_string()))
+    }
+}
+
+fn invalid_data(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+/// Parse a size as printed by cargo, e.g. `512B` or `8.9MiB`
+fn parse_size(size: &str) -> io::Result<u64> {
+    const UNITS: [(&str, i32); 7] = [
+        ("KiB", 1),
+        ("MiB", 2),
+        ("GiB", 3),
+        ("TiB", 4),
+        ("PiB", 5),
+        ("EiB", 6),
+        ("B", 0),
+    ];
+    let (value, exp) = UNITS
+        .iter()
+        .find_map(|(unit, exp)| size.strip_suffix(unit).map(|value| (value, *exp)))
+        .ok_or_else(|| invalid_data(format!("invalid size `{}`", size)))?;
+    let value: f64 = value
+        .parse()
+        .map_err(|_| invalid_data(format!("invalid size `{}`", size)))?;
+    Ok((value * 1024f64.powi(exp)).round() as u64)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Clean, CleanSummary};
+    use clap::{CommandFactory, Parser};
+
+    #[test]
+    fn verify_cli() {
+        <Clean as CommandFactory>::command().debug_assert()
+    }
+
+    #[test]
+    fn glibc_target() {
+        let clean = Clean::parse_from(["clean", "--target", "x86_64-unknown-linux-gnu.2.17"]);
+        let args: Vec<_> = clean.command().get_args().map(|a| a.to_owned()).collect();
+        assert_eq!(args, ["clean", "--target", "x86_64-unknown-linux-gnu"]);
+    }
+
+    #[test]
+    fn parse_summary() {
+        let stderr = "     Summary 28 files, 8.9MiB total\n\
+                      warning: no files deleted due to --dry-run\n";
+        let summary: CleanSummary = stderr.parse().unwrap();
+        assert_eq!(
+            summary,
+            CleanSummary {
+                dry_run: true,
+                files: 28,
+                directories: 0,
+                bytes: 9332326,
+            }
+        );
+
+        let summary: CleanSummary = "     Removed 1 file, 3 directories, 512B total"
+            .parse()
+            .unwrap();
+        assert!(!summary.dry_run);
+        assert_eq!(summary.files, 1);
+        assert_eq!(summary.directories, 3);
+        assert_eq!(summary.bytes, 512);
+
+        let summary: CleanSummary = "     Summary 0 files".parse().unwrap();
+        assert_eq!(summary.files, 0);
+        assert_eq!(summary.bytes, 0);
+    }
+}
@@ -1,30 +1,35 @@
-use std::path::PathBuf;
-use std::process::Command;
+use std::collections::BTreeMap;
+use std::env;
+use std::io::{self, Write};
+use std::ops::{Deref, DerefMut};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
 
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
+use crate::cargo::CargoVersion;
+use crate::config_override::ConfigOverride;
 use crate::heading;
-use clap::{ArgAction, Parser};
+use clap::parser::ValueSource;
+use clap::{ArgAction, ArgMatches, Parser};
 
 /// common cargo options
 #[derive(Clone, Debug, Default, Parser)]
 #[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
 pub struct CommonOptions {
-    /// Do not print cargo log messages
-    #[arg(short = 'q', long)]
-    #[cfg_attr(feature = "serde", serde(default))]
-    pub quiet: bool,
-
     /// Number of parallel jobs, defaults to # of CPUs
     #[arg(
         short = 'j',
         long,
         value_name = "N",
+        allow_negative_numbers = true,
+        value_parser = parse_jobs,
         help_heading = heading::COMPILATION_OPTIONS,
     )]
+    #[cfg_attr(feature = "env", arg(env = "CARGO_BUILD_JOBS"))]
     #[cfg_attr(feature = "serde", serde(default))]
-    pub jobs: Option<usize>,
+    pub jobs: Option<String>,
 
     /// Do not abort the build as soon as there is an error (unstable)
     #[arg(long, help_heading = heading::COMPILATION_OPTIONS)]
@@ -40,43 +45,29 @@ pub struct CommonOptions {
     #[cfg_attr(feature = "serde", serde(default))]
     pub profile: Option<String>,
 
-    /// Space or comma separated list of features to activate
-    #[arg(
-        short = 'F',
-        long,
-        action = ArgAction::Append,
-        help_heading = heading::FEATURE_SELECTION,
-    )]
-    #[cfg_attr(feature = "serde", serde(default))]
-    pub features: Vec<String>,
-
-    /// Activate all available features
-    #[arg(long, help_heading = heading::FEATURE_SELECTION)]
-    #[cfg_attr(feature = "serde", serde(default))]
-    pub all_features: bool,
-
-    /// Do not activate the `default` feature
-    #[arg(long, help_heading = heading::FEATURE_SELECTION)]
-    #[cfg_attr(feature = "serde", serde(default))]
-    pub no_default_features: bool,
+    #[command(flatten)]
+    #[cfg_attr(feature = "serde", serde(flatten))]
+    pub feature_selection: FeatureSelection,
 
     /// Build for the target triple
     #[arg(
         long,
         value_name = "TRIPLE",
-        env = "CARGO_BUILD_TARGET",
         action = ArgAction::Append,
         help_heading = heading::COMPILATION_OPTIONS,
     )]
+    #[cfg_attr(feature = "env", arg(env = "CARGO_BUILD_TARGET"))]
     #[cfg_attr(feature = "serde", serde(default))]
     pub target: Vec<String>,
 
     /// Directory for all generated artifacts
+    // clap binds one variable per option, `CARGO_BUILD_TARGET_DIR` is left unbound
     #[arg(
         long,
         value_name = "DIRECTORY",
         help_heading = heading::COMPILATION_OPTIONS,
     )]
+    #[cfg_attr(feature = "env", arg(env = "CARGO_TARGET_DIR"))]
     #[cfg_attr(feature = "serde", serde(default))]
     pub target_dir: Option<PathBuf>,
 
@@ -85,13 +76,219 @@ pub struct CommonOptions {
     #[cfg_attr(feature = "serde", serde(default))]
     pub message_format: Vec<String>,
 
+    /// Timing output formats (unstable) (comma separated): html, json
+    #[arg(
+        long,
+        value_name = "FMTS",
+        num_args = 0..,
+        value_delimiter = ',',
+        require_equals = true,
+        help_heading = heading::COMPILATION_OPTIONS,
+    )]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub timings: Option<Vec<String>>,
+
+    #[command(flatten)]
+    #[cfg_attr(feature = "serde", serde(flatten))]
+    pub global_options: GlobalOptions,
+}
+
+impl CommonOptions {
+    /// Name of the profile `--profile` or `release` selects, `dev` otherwise
+    ///
+    /// `cargo test` and `cargo bench` default to the `test` and `bench`
+    /// profiles, which inherit from `dev` and `release`.
+    pub fn profile_name(&self, release: bool) -> &str {
+        match self.profile.as_deref() {
+            Some(profile) => profile,
+            None if release => "release",
+            None => "dev",
+        }
+    }
+
+    /// Unstable options in use, cargo only takes them with `-Z unstable-options`
+    pub fn unstable_options(&self) -> Vec<&'static str> {
+        let mut options = self.global_options.unstable_options();
+        if self.timings.as_ref().is_some_and(|t| !t.is_empty()) {
+            options.push("--timings=<FMTS>");
+        }
+        options
+    }
+
+    /// Whether the options only work on a nightly cargo
+    pub fn requires_nightly(&self) -> bool {
+        self.requires_nightly_for(&self.unstable_options())
+    }
+
+    /// Apply options to `Command`
+    pub fn apply(&self, cmd: &mut Command) {
+        self.apply_with_unstable(cmd, &self.unstable_options());
+    }
+
+    /// Apply options to `Command`, adding `-Z unstable-options` once if
+    /// any of `unstable_options` are in use
+    pub(crate) fn apply_with_unstable(&self, cmd: &mut Command, unstable_options: &[&str]) {
+        self.global_options
+            .apply_with_unstable(cmd, unstable_options);
+        if let Some(jobs) = self.jobs_arg() {
+            cmd.arg("--jobs").arg(jobs);
+        }
+        if self.keep_going {
+            cmd.arg("--keep-going");
+        }
+        if let Some(profile) = self.profile.as_ref() {
+            cmd.arg("--profile").arg(profile);
+        }
+        self.feature_selection.apply(cmd);
+        for target in self.target_args() {
+            cmd.arg("--target").arg(target);
+        }
+        if let Some(dir) = self.target_dir_arg() {
+            cmd.arg("--target-dir").arg(dir);
+        }
+        for fmt in &self.message_format {
+            cmd.arg("--message-format").arg(fmt);
+        }
+        if let Some(timings) = self.timings_arg() {
+            cmd.arg(timings);
+        }
+    }
+
+    /// `--jobs` value to pass on, `None` if it was read from `CARGO_BUILD_JOBS`
+    pub(crate) fn jobs_arg(&self) -> Option<&str> {
+        self.jobs
+            .as_deref()
+            .filter(|_| !self.provenance.is_from_env("jobs"))
+    }
+
+    /// `--target` values to pass on, see [`target_args`]
+    pub(crate) fn target_args(&self) -> Vec<&str> {
+        target_args(&self.target, &self.provenance)
+    }
+
+    /// `--target-dir` to pass on, `None` if it was read from `CARGO_TARGET_DIR`
+    pub(crate) fn target_dir_arg(&self) -> Option<&Path> {
+        self.target_dir
+            .as_deref()
+            .filter(|_| !self.provenance.is_from_env("target_dir"))
+    }
+
+    /// `--timings` or `--timings=<FMTS>`
+    pub(crate) fn timings_arg(&self) -> Option<String> {
+        let timings = self.timings.as_ref()?;
+        if timings.is_empty() {
+            Some("--timings".to_string())
+        } else {
+            Some(format!("--timings={}", timings.join(",")))
+        }
+    }
+
+    /// Check options against `version`, returning whether any of them
+    /// needs `-Z unstable-options` there
+    ///
+    /// Options `version` doesn't have (anymore) are an error.
+    pub fn check_version(&self, version: &CargoVersion) -> io::Result<bool> {
+        let mut unstable = self.global_options.check_version(version)?;
+        if self.keep_going {
+            if !version.is_at_least(1, 61) {
+                return Err(unsupported(
+                    "--keep-going",
+                    "was added in cargo 1.61",
+                    version,
+                ));
+            }
+            unstable |= !version.is_at_least(1, 74);
+        }
+        if let Some(timings) = self.timings.as_ref() {
+            if !version.is_at_least(1, 60) {
+                return Err(unsupported("--timings", "was added in cargo 1.60", version));
+            }
+            if !timings.is_empty() {
+                if version.is_at_least(1, 94) {
+                    return Err(unsupported(
+                        "--timings=<FMTS>",
+                        "was removed in cargo 1.94",
+                        version,
+                    ));
+                }
+                unstable = true;
+            }
+        }
+        Ok(unstable)
+    }
+
+    /// Prepare the options for `version`, adding `-Z unstable-options` when
+    /// one of them is unstable there, see [`check_version`](Self::check_version)
+    pub fn adapt_to_version(&mut self, version: &CargoVersion) -> io::Result<()> {
+        if self.check_version(version)? && !self.no_auto_unstable_options {
+            self.enable_unstable_options();
+        }
+        Ok(())
+    }
+
+    pub(crate) fn cargo_command() -> Command {
+        let cargo = match std::env::var_os("CARGO") {
+            Some(cargo) => cargo.into(),
+            None => PathBuf::from("cargo"),
+        };
+        let mut cmd = Command::new(cargo);
+        cmd.env_remove("CARGO");
+        cmd
+    }
+
+    /// `cargo +<toolchain>` through the rustup proxy, `CARGO` points at a
+    /// specific toolchain's cargo which doesn't understand `+toolchain`
+    pub(crate) fn toolchain_cargo_command(toolchain: Option<&str>) -> Command {
+        match toolchain {
+            Some(toolchain) => {
+                let mut cmd = Command::new("cargo");
+                cmd.env_remove("CARGO");
+                cmd.arg(format!("+{}", toolchain));
+                cmd
+            }
+            None => Self::cargo_command(),
+        }
+    }
+}
+
+impl Deref for CommonOptions {
+    type Target = GlobalOptions;
+
+    fn deref(&self) -> &Self::Target {
+        &self.global_options
+    }
+}
+
+impl DerefMut for CommonOptions {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.global_options
+    }
+}
+
+/// Options accepted by every cargo subcommand
+#[derive(Clone, Debug, Default, Parser)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct GlobalOptions {
+    /// Do not print cargo log messages
+    #[arg(short = 'q', long)]
+    #[cfg_attr(feature = "env", arg(env = "CARGO_TERM_QUIET"))]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub quiet: bool,
+
     /// Use verbose output (-vv very verbose/build.rs output)
-    #[arg(short = 'v', long, action = ArgAction::Count)]
+    #[arg(
+        short = 'v',
+        long,
+        action = ArgAction::Count,
+        value_parser = parse_verbose,
+    )]
+    #[cfg_attr(feature = "env", arg(env = "CARGO_TERM_VERBOSE"))]
     #[cfg_attr(feature = "serde", serde(default))]
     pub verbose: u8,
 
     /// Coloring: auto, always, never
     #[arg(long, value_name = "WHEN")]
+    #[cfg_attr(feature = "env", arg(env = "CARGO_TERM_COLOR"))]
     #[cfg_attr(feature = "serde", serde(default))]
     pub color: Option<String>,
 
@@ -107,6 +304,7 @@ pub struct CommonOptions {
 
     /// Run without accessing the network
     #[arg(long, help_heading = heading::MANIFEST_OPTIONS)]
+    #[cfg_attr(feature = "env", arg(env = "CARGO_NET_OFFLINE"))]
     #[cfg_attr(feature = "serde", serde(default))]
     pub offline: bool,
 
@@ -120,65 +318,162 @@ pub struct CommonOptions {
     #[cfg_attr(feature = "serde", serde(default))]
     pub unstable_flags: Vec<String>,
 
-    /// Timing output formats (unstable) (comma separated): html, json
+    /// Change to DIRECTORY before doing anything (nightly-only)
+    #[arg(short = 'C', value_name = "DIRECTORY", global = true)]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub directory: Option<PathBuf>,
+
+    /// Path to Cargo.lock (unstable)
     #[arg(
         long,
-        value_name = "FMTS",
-        num_args = 0..,
-        value_delimiter = ',',
-        require_equals = true,
-        help_heading = heading::COMPILATION_OPTIONS,
+        value_name = "PATH",
+        global = true,
+        help_heading = heading::MANIFEST_OPTIONS,
     )]
     #[cfg_attr(feature = "serde", serde(default))]
-    pub timings: Option<Vec<String>>,
+    pub lockfile_path: Option<PathBuf>,
+
+    /// Don't add `-Z unstable-options` when unstable options are used
+    #[arg(skip)]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub no_auto_unstable_options: bool,
+
+    /// Where each option came from, see [`Provenance::from_matches`]
+    #[arg(skip)]
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub provenance: Provenance,
 }
 
-impl CommonOptions {
-    /// Apply options to `Command`
-    pub fn apply(&self, cmd: &mut Command) {
-        if self.quiet {
-            cmd.arg("--quiet");
-        }
-        if let Some(jobs) = self.jobs {
-            cmd.arg("--jobs").arg(jobs.to_string());
+impl GlobalOptions {
+    /// Add a typed `--config` override
+    pub fn push_config(&mut self, config: ConfigOverride) {
+        self.config.push(config.to_string());
+    }
+
+    /// Parse the `--config` arguments back into typed overrides
+    pub fn config_overrides(&self) -> io::Result<Vec<ConfigOverride>> {
+        self.config.iter().map(|config| config.parse()).collect()
+    }
+
+    /// Add `-Z unstable-options` unless it's already there
+    pub(crate) fn enable_unstable_options(&mut self) {
+        if !self.unstable_flags.iter().any(|f| f == "unstable-options") {
+            self.unstable_flags.push("unstable-options".to_string());
         }
-        if self.keep_going {
-            cmd.arg("--keep-going");
+    }
+
+    /// Check options against `version`, returning whether any of them
+    /// needs `-Z unstable-options` there
+    ///
+    /// Options `version` doesn't have (anymore) are an error.
+    pub fn check_version(&self, version: &CargoVersion) -> io::Result<bool> {
+        let mut unstable = false;
+        if self.directory.is_some() {
+            if !version.is_at_least(1, 69) {
+                return Err(unsupported("-C", "was added in cargo 1.69", version));
+            }
+            unstable = true;
         }
-        if let Some(profile) = self.profile.as_ref() {
-            cmd.arg("--profile").arg(profile);
+        if self.lockfile_path.is_some() {
+            if !version.is_at_least(1, 82) {
+                return Err(unsupported(
+                    "--lockfile-path",
+                    "was added in cargo 1.82",
+                    version,
+                ));
+            }
+            if version.is_at_least(1, 95) {
+                return Err(unsupported(
+                    "--lockfile-path",
+                    "was removed in cargo 1.95, use the `resolver.lockfile-path` config instead",
+                    version,
+                ));
+            }
+            unstable = true;
         }
-        for feature in &self.features {
-            cmd.arg("--features").arg(feature);
+        Ok(unstable)
+    }
+
+    /// Apply options cargo only accepts before the subcommand to `Command`
+    pub fn apply_top_level(&self, cmd: &mut Command) {
+        if let Some(dir) = self.directory.as_ref() {
+            cmd.arg("-C").arg(dir);
         }
-        if self.all_features {
-            cmd.arg("--all-features");
+    }
+
+    /// Unstable options in use, cargo only takes them with `-Z unstable-options`
+    pub fn unstable_options(&self) -> Vec<&'static str> {
+        let mut options = Vec::new();
+        if self.directory.is_some() {
+            options.push("-C");
         }
-        if self.no_default_features {
-            cmd.arg("--no-default-features");
+        if self.lockfile_path.is_some() {
+            options.push("--lockfile-path");
         }
+        options
+    }
 
-        // Support <target_triple>.<glibc_version> syntax
-        // For example: x86_64-unknown-linux-gnu.2.17
-        let rust_targets = self
-            .target
-            .iter()
-            .map(|target| target.split_once('.').map(|(t, _)| t).unwrap_or(target))
-            .collect::<Vec<&str>>();
-        rust_targets.iter().for_each(|target| {
-            cmd.arg("--target").arg(target);
-        });
+    /// Whether the options only work on a nightly cargo
+    pub fn requires_nightly(&self) -> bool {
+        self.requires_nightly_for(&self.unstable_options())
+    }
 
-        if let Some(dir) = self.target_dir.as_ref() {
-            cmd.arg("--target-dir").arg(dir);
+    pub(crate) fn requires_nightly_for(&self, unstable_options: &[&str]) -> bool {
+        !unstable_options.is_empty() || !self.unstable_flags.is_empty()
+    }
+
+    /// Apply options to `Command`
+    pub fn apply(&self, cmd: &mut Command) {
+        self.apply_with_unstable(cmd, &self.unstable_options());
+    }
+
+    /// `--quiet` and the number of `-v` to pass on
+    ///
+    /// cargo rejects `--quiet` with `--verbose`, but lets either of them
+    /// override `term.quiet` and `term.verbose`. When both are set, the one
+    /// that came from `CARGO_TERM_*` gives way, going by whether the
+    /// variable is set if `provenance` wasn't filled in.
+    pub(crate) fn quiet_and_verbose(&self) -> (bool, u8) {
+        let mut quiet = self.quiet && !self.provenance.is_from_env("quiet");
+        let mut verbose = if self.provenance.is_from_env("verbose") {
+            0
+        } else {
+            self.verbose
+        };
+        if quiet && verbose > 0 {
+            let unrecorded_env = |id, var| {
+                cfg!(feature = "env")
+                    && self.provenance.get(id).is_none()
+                    && env::var_os(var).is_some()
+            };
+            if unrecorded_env("quiet", "CARGO_TERM_QUIET") {
+                quiet = false;
+            } else if unrecorded_env("verbose", "CARGO_TERM_VERBOSE") {
+                verbose = 0;
+            }
         }
-        for fmt in &self.message_format {
-            cmd.arg("--message-format").arg(fmt);
+        (quiet, verbose)
+    }
+
+    /// Apply options to `Command`, adding `-Z unstable-options` once if
+    /// any of `unstable_options` are in use
+    pub(crate) fn apply_with_unstable(&self, cmd: &mut Command, unstable_options: &[&str]) {
+        let (quiet, verbose) = self.quiet_and_verbose();
+        if quiet {
+            cmd.arg("--quiet");
         }
-        if self.verbose > 0 {
-            cmd.arg(format!("-{}", "v".repeat(self.verbose.into())));
+        if verbose > 0 {
+            cmd.arg(format!("-{}", "v".repeat(verbose.into())));
         }
-        if let Some(color) = self.color.as_ref() {
+        self.apply_without_verbosity(cmd, unstable_options);
+    }
+
+    /// [`apply_with_unstable`](Self::apply_with_unstable) without `--quiet`
+    /// and `--verbose`, which wrappers like nextest spell differently
+    pub(crate) fn apply_without_verbosity(&self, cmd: &mut Command, unstable_options: &[&str]) {
+        // Values read from the environment are left for cargo to read again
+        let from_cli = |id| !self.provenance.is_from_env(id);
+        if let Some(color) = self.color.as_ref().filter(|_| from_cli("color")) {
             cmd.arg("--color").arg(color);
         }
         if self.frozen {
@@ -187,32 +482,754 @@ impl CommonOptions {
         if self.locked {
             cmd.arg("--locked");
         }
-        if self.offline {
+        if self.offline && from_cli("offline") {
             cmd.arg("--offline");
         }
+        if let Some(path) = self.lockfile_path.as_ref() {
+            cmd.arg("--lockfile-path").arg(path);
+        }
         for config in &self.config {
             cmd.arg("--config").arg(config);
         }
         for flag in &self.unstable_flags {
             cmd.arg("-Z").arg(flag);
         }
-        if let Some(timings) = &self.timings {
-            if timings.is_empty() {
-                cmd.arg("--timings");
-            } else {
-                let timings: Vec<_> = timings.iter().map(|x| x.as_str()).collect();
-                cmd.arg(format!("--timings={}", timings.join(",")));
+        if self.needs_unstable_options(unstable_options) {
+            cmd.arg("-Z").arg("unstable-options");
+        }
+    }
+
+    /// Whether `-Z unstable-options` has to be added for `unstable_options`
+    pub(crate) fn needs_unstable_options(&self, unstable_options: &[&str]) -> bool {
+        !unstable_options.is_empty()
+            && !self.no_auto_unstable_options
+            && !self.unstable_flags.iter().any(|f| f == "unstable-options")
+    }
+}
+
+/// Where an option's value came from
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
+pub enum OptionSource {
+    /// Passed on the command line
+    CommandLine,
+    /// Read from an environment variable, e.g. `CARGO_BUILD_TARGET`
+    Environment,
+    /// Loaded from a config file by the caller, clap never reports this one
+    ConfigFile,
+    /// clap's default value
+    Default,
+}
+
+impl From<ValueSource> for OptionSource {
+    fn from(source: ValueSource) -> Self {
+        match source {
+            ValueSource::CommandLine => OptionSource::CommandLine,
+            ValueSource::EnvVariable => OptionSource::Environment,
+            _ => OptionSource::Default,
+        }
+    }
+}
+
+/// Where each option of a parsed struct came from, keyed by clap argument
+/// id, which is the field name
+///
+/// clap doesn't hand this to derived structs, fill it in after parsing.
+/// Options set in code afterwards should have their entry removed:
+///
+/// ```
+/// use cargo_options::{Build, Provenance};
+/// use clap::{CommandFactory, FromArgMatches};
+///
+/// let matches = <Build as CommandFactory>::command().get_matches_from(["build", "-r"]);
+/// let mut build = Build::from_arg_matches(&matches).unwrap();
+/// build.provenance = Provenance::from_matches(&matches);
+///
+/// build.target_dir = Some("isolated".into());
+/// build.provenance.remove("target_dir");
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct Provenance {
+    sources: BTreeMap<String, OptionSource>,
+}
+
+impl Provenance {
+    /// Record the source of every argument in `matches`
+    pub fn from_matches(matches: &ArgMatches) -> Self {
+        let sources = matches
+            .ids()
+            .filter_map(|id| {
+                let source = matches.value_source(id.as_str())?;
+                Some((id.to_string(), source.into()))
+            })
+            .collect();
+        Provenance { sources }
+    }
+
+    /// Source of argument `id`, `None` when it's unset and has no default
+    pub fn get(&self, id: &str) -> Option<OptionSource> {
+        self.sources.get(id).copied()
+    }
+
+    /// Record the source of argument `id`, e.g. values merged from a config file
+    pub fn insert(&mut self, id: impl Into<String>, source: OptionSource) {
+        self.sources.insert(id.into(), source);
+    }
+
+    /// Forget where argument `id` came from, e.g. after setting it in code
+    pub fn remove(&mut self, id: &str) -> Option<OptionSource> {
+        self.sources.remove(id)
+    }
+
+    /// Argument ids and their sources, sorted by id
+    pub fn iter(&self) -> impl Iterator<Item = (&str, OptionSource)> {
+        self.sources
+            .iter()
+            .map(|(id, source)| (id.as_str(), *source))
+    }
+
+    /// Whether `id` was only read from the environment, which cargo reads too
+    pub(crate) fn is_from_env(&self, id: &str) -> bool {
+        self.get(id) == Some(OptionSource::Environment)
+    }
+}
+
+/// Feature selection options
+#[derive(Clone, Debug, Default, Parser)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct FeatureSelection {
+    /// Space or comma separated list of features to activate
+    #[arg(
+        short = 'F',
+        long,
+        action = ArgAction::Append,
+        help_heading = heading::FEATURE_SELECTION,
+    )]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub features: Vec<String>,
+
+    /// Activate all available features
+    #[arg(long, help_heading = heading::FEATURE_SELECTION)]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub all_features: bool,
+
+    /// Do not activate the `default` feature
+    #[arg(long, help_heading = heading::FEATURE_SELECTION)]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub no_default_features: bool,
+}
+
+impl FeatureSelection {
+    /// Apply options to `Command`
+    pub fn apply(&self, cmd: &mut Command) {
+        for feature in &self.features {
+            cmd.arg("--features").arg(feature);
+        }
+        if self.all_features {
+            cmd.arg("--all-features");
+        }
+        if self.no_default_features {
+            cmd.arg("--no-default-features");
+        }
+    }
+}
+
+/// Package selection options
+#[derive(Clone, Debug, Default, Parser)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct PackageSelection {
+    /// Package(s) to operate on (see `cargo help pkgid`)
+    #[arg(
+        short = 'p',
+        long = "package",
+        value_name = "SPEC",
+        action = ArgAction::Append,
+        num_args=0..=1,
+        help_heading = heading::PACKAGE_SELECTION,
+    )]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub packages: Vec<String>,
+
+    /// Select all packages in the workspace
+    #[arg(long, help_heading = heading::PACKAGE_SELECTION)]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub workspace: bool,
+
+    /// Exclude packages from the build
+    #[arg(
+        long,
+        value_name = "SPEC",
+        action = ArgAction::Append,
+        help_heading = heading::PACKAGE_SELECTION,
+    )]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub exclude: Vec<String>,
+
+    /// Alias for --workspace (deprecated)
+    #[arg(long, help_heading = heading::PACKAGE_SELECTION)]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub all: bool,
+}
+
+impl PackageSelection {
+    /// Apply options to `Command`
+    pub fn apply(&self, cmd: &mut Command) {
+        for pkg in &self.packages {
+            cmd.arg("--package").arg(pkg);
+        }
+        if self.workspace {
+            cmd.arg("--workspace");
+        }
+        for item in &self.exclude {
+            cmd.arg("--exclude").arg(item);
+        }
+        if self.all {
+            cmd.arg("--all");
+        }
+    }
+}
+
+/// Library, binary and example target selection, the targets all
+/// commands building a package can select
+#[derive(Clone, Debug, Default, Parser)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct LibBinExampleSelection {
+    /// Select only this package's library
+    #[arg(long, help_heading = heading::TARGET_SELECTION)]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub lib: bool,
+
+    /// Select only the specified binary
+    #[arg(
+        long,
+        value_name = "NAME",
+        action = ArgAction::Append,
+        num_args=0..=1,
+        help_heading = heading::TARGET_SELECTION,
+    )]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub bin: Vec<String>,
+
+    /// Select all binaries
+    #[arg(long, help_heading = heading::TARGET_SELECTION)]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub bins: bool,
+
+    /// Select only the specified example
+    #[arg(
+        long,
+        value_name = "NAME",
+        action = ArgAction::Append,
+        num_args=0..=1,
+        help_heading = heading::TARGET_SELECTION,
+    )]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub example: Vec<String>,
+
+    /// Select all examples
+    #[arg(long, help_heading = heading::TARGET_SELECTION)]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub examples: bool,
+}
+
+impl LibBinExampleSelection {
+    /// Apply options to `Command`
+    pub fn apply(&self, cmd: &mut Command) {
+        if self.lib {
+            cmd.arg("--lib");
+        }
+        for bin in &self.bin {
+            cmd.arg("--bin").arg(bin);
+        }
+        if self.bins {
+            cmd.arg("--bins");
+        }
+        for example in &self.example {
+            cmd.arg("--example").arg(example);
+        }
+        if self.examples {
+            cmd.arg("--examples");
+        }
+    }
+}
+
+/// Target selection options
+#[derive(Clone, Debug, Default, Parser)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct TargetSelection {
+    #[command(flatten)]
+    #[cfg_attr(feature = "serde", serde(flatten))]
+    pub lib_bin_example: LibBinExampleSelection,
+
+    /// Select only the specified test target
+    #[arg(
+        long,
+        value_name = "NAME",
+        action = ArgAction::Append,
+        num_args=0..=1,
+        help_heading = heading::TARGET_SELECTION,
+    )]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub test: Vec<String>,
+
+    /// Select all tests
+    #[arg(long, help_heading = heading::TARGET_SELECTION)]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub tests: bool,
+
+    /// Select only the specified bench target
+    #[arg(
+        long,
+        value_name = "NAME",
+        action = ArgAction::Append,
+        num_args=0..=1,
+        help_heading = heading::TARGET_SELECTION,
+    )]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub bench: Vec<String>,
+
+    /// Select all benches
+    #[arg(long, help_heading = heading::TARGET_SELECTION)]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub benches: bool,
+
+    /// Select all targets
+    #[arg(long, help_heading = heading::TARGET_SELECTION)]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub all_targets: bool,
+}
+
+impl TargetSelection {
+    /// Apply options to `Command`
+    pub fn apply(&self, cmd: &mut Command) {
+        self.lib_bin_example.apply(cmd);
+        for test in &self.test {
+            cmd.arg("--test").arg(test);
+        }
+        if self.tests {
+            cmd.arg("--tests");
+        }
+        for bench in &self.bench {
+            cmd.arg("--bench").arg(bench);
+        }
+        if self.benches {
+            cmd.arg("--benches");
+        }
+        if self.all_targets {
+            cmd.arg("--all-targets");
+        }
+    }
+}
+
+impl Deref for TargetSelection {
+    type Target = LibBinExampleSelection;
+
+    fn deref(&self) -> &Self::Target {
+        &self.lib_bin_example
+    }
+}
+
+impl DerefMut for TargetSelection {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.lib_bin_example
+    }
+}
+
+/// Manifest options
+#[derive(Clone, Debug, Default, Parser)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct ManifestOptions {
+    /// Path to Cargo.toml
+    #[arg(long, value_name = "PATH", help_heading = heading::MANIFEST_OPTIONS)]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub manifest_path: Option<PathBuf>,
+
+    /// Ignore `rust-version` specification in packages
+    #[arg(long, help_heading = heading::MANIFEST_OPTIONS)]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub ignore_rust_version: bool,
+}
+
+impl ManifestOptions {
+    /// Apply options to `Command`
+    pub fn apply(&self, cmd: &mut Command) {
+        if let Some(path) = self.manifest_path.as_ref() {
+            cmd.arg("--manifest-path").arg(path);
+        }
+        if self.ignore_rust_version {
+            cmd.arg("--ignore-rust-version");
+        }
+    }
+}
+
+/// Compilation options not shared with `cargo install`
+#[derive(Clone, Debug, Default, Parser)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct CompilationOptions {
+    /// Build artifacts in release mode, with optimizations
+    #[arg(short = 'r', long, help_heading = heading::COMPILATION_OPTIONS)]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub release: bool,
+
+    /// Output build graph in JSON (unstable)
+    #[arg(long, help_heading = heading::COMPILATION_OPTIONS)]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub unit_graph: bool,
+}
+
+impl CompilationOptions {
+    /// Unstable options in use, cargo only takes them with `-Z unstable-options`
+    pub fn unstable_options(&self) -> Vec<&'static str> {
+        let mut options = Vec::new();
+        if self.unit_graph {
+            options.push("--unit-graph");
+        }
+        options
+    }
+
+    /// Apply options to `Command`
+    pub fn apply(&self, cmd: &mut Command) {
+        if self.release {
+            cmd.arg("--release");
+        }
+        if self.unit_graph {
+            cmd.arg("--unit-graph");
+        }
+    }
+}
+
+/// Strip the glibc version from the `<target_triple>.<glibc_version>` syntax
+///
+/// For example: `x86_64-unknown-linux-gnu.2.17` becomes `x86_64-unknown-linux-gnu`
+pub(crate) fn rust_target(target: &str) -> &str {
+    target.split_once('.').map(|(t, _)| t).unwrap_or(target)
+}
+
+/// `--target` values to pass on, normalized with [`rust_target`]
+///
+/// Targets read from `CARGO_BUILD_TARGET` are left for cargo to read again,
+/// unless normalizing changed them.
+pub(crate) fn target_args<'a>(targets: &'a [String], provenance: &Provenance) -> Vec<&'a str> {
+    targets
+        .iter()
+        .map(|target| (target, rust_target(target)))
+        .filter(|(target, rust_target)| !provenance.is_from_env("target") || rust_target != target)
+        .map(|(_, rust_target)| rust_target)
+        .collect()
+}
+
+/// Parse a `-v` count, or a `CARGO_TERM_VERBOSE` boolean
+fn parse_verbose(s: &str) -> Result<u8, String> {
+    match s {
+        "true" => Ok(1),
+        "false" => Ok(0),
+        _ => s
+            .parse()
+            .map_err(|_| format!("expected `true` or `false`, found `{}`", s)),
+    }
+}
+
+/// Parse a `-j` value, a non-zero job count where negative ones are
+/// subtracted from the number of CPUs, or `default`
+fn parse_jobs(s: &str) -> Result<String, String> {
+    match s.parse::<i32>() {
+        Ok(0) => Err("jobs may not be 0".to_string()),
+        Ok(_) => Ok(s.to_string()),
+        Err(_) if s == "default" => Ok(s.to_string()),
+        Err(_) => Err(format!("expected a number or `default`, found `{}`", s)),
+    }
+}
+
+/// Best guess at the target directory cargo will use
+///
+/// Taken from `--target-dir`, then `CARGO_TARGET_DIR`/`CARGO_BUILD_TARGET_DIR`,
+/// and finally `target` next to `manifest_path` (or the current directory).
+/// That fallback does not know about workspace roots or `build.target-dir`
+/// set in config files.
+pub(crate) fn target_dir(target_dir: Option<&Path>, manifest_path: Option<&Path>) -> PathBuf {
+    match target_dir {
+        Some(dir) => dir.to_path_buf(),
+        None => env::var_os("CARGO_TARGET_DIR")
+            .or_else(|| env::var_os("CARGO_BUILD_TARGET_DIR"))
+            .map(PathBuf::from)
+            .unwrap_or_else(|| {
+                manifest_path
+                    .and_then(Path::parent)
+                    .unwrap_or_else(|| Path::new(""))
+                    .join("target")
+            }),
+    }
+}
+
+/// Error for an option `version` doesn't know about
+pub(crate) fn unsupported(flag: &str, reason: &str, version: &CargoVersion) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::Unsupported,
+        format!("`{}` {}, found cargo {}", flag, reason, version.release),
+    )
+}
+
+/// Run `cmd` to completion and return its stdout,
+/// turning a non-zero exit status into an error carrying its stderr
+pub(crate) fn capture_stdout(cmd: &mut Command) -> io::Result<String> {
+    let output = cmd.output()?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(io::Error::other(format!(
+            "{:?} failed with {}: {}",
+            cmd,
+            output.status,
+            stderr.trim()
+        )));
+    }
+    String::from_utf8(output.stdout).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+}
+
+/// Registry name `--index` is rewritten to when a token has to be passed,
+/// cargo only reads tokens for bare index URLs from `--token`
+pub(crate) const INDEX_REGISTRY: &str = "cargo-options-index";
+
+/// Add `--index`/`--registry`, using [`INDEX_REGISTRY`] for the index when
+/// a token will be passed through the environment
+pub(crate) fn apply_registry(
+    cmd: &mut Command,
+    registry: Option<&str>,
+    index: Option<&str>,
+    token: bool,
+) {
+    match (index, token) {
+        (Some(index), true) => {
+            cmd.arg("--registry").arg(INDEX_REGISTRY);
+            let config = ConfigOverride::new(["registries", INDEX_REGISTRY, "index"], index);
+            cmd.arg("--config").arg(config.to_string());
+        }
+        (Some(index), false) => {
+            cmd.arg("--index").arg(index);
+        }
+        (None, _) => {}
+    }
+    if let Some(registry) = registry {
+        cmd.arg("--registry").arg(registry);
+    }
+}
+
+/// Environment variable cargo reads the API token of `registry` from
+pub(crate) fn registry_token_env(registry: Option<&str>, index: Option<&str>) -> String {
+    let registry = if index.is_some() {
+        Some(INDEX_REGISTRY)
+    } else {
+        registry
+    };
+    match registry {
+        Some(name) => format!(
+            "CARGO_REGISTRIES_{}_TOKEN",
+            name.to_uppercase().replace('-', "_")
+        ),
+        None => "CARGO_REGISTRY_TOKEN".to_string(),
+    }
+}
+
+/// Run `cmd` to completion, writing `stdin` to it when given.
+///
+/// Errors only mention the program and its arguments, not the environment,
+/// which may carry a token.
+pub(crate) fn run_with_secret(cmd: &mut Command, stdin: Option<&str>) -> io::Result<()> {
+    let status = match stdin {
+        Some(input) => {
+            cmd.stdin(Stdio::piped());
+            let mut child = cmd.spawn()?;
+            if let Some(mut pipe) = child.stdin.take() {
+                writeln!(pipe, "{}", input)?;
             }
+            child.wait()?
         }
+        None => cmd.status()?,
+    };
+    if status.success() {
+        return Ok(());
+    }
+    let mut line = cmd.get_program().to_string_lossy().into_owned();
+    for arg in cmd.get_args() {
+        line.push(' ');
+        line.push_str(&arg.to_string_lossy());
     }
+    Err(io::Error::other(format!(
+        "`{}` failed with {}",
+        line, status
+    )))
+}
 
-    pub(crate) fn cargo_command() -> Command {
-        let cargo = match std::env::var_os("CARGO") {
-            Some(cargo) => cargo.into(),
-            None => PathBuf::from("cargo"),
+#[cfg(test)]
+mod test {
+    #[cfg(feature = "env")]
+    use super::parse_verbose;
+    use super::{parse_jobs, ManifestOptions, OptionSource, PackageSelection, Provenance};
+    use crate::Build;
+    use clap::{CommandFactory, FromArgMatches, Parser};
+    use std::process::Command;
+
+    /// A custom subcommand picking only the groups it needs
+    #[derive(Debug, Parser)]
+    struct Custom {
+        #[command(flatten)]
+        manifest_options: ManifestOptions,
+
+        #[command(flatten)]
+        package_selection: PackageSelection,
+    }
+
+    #[test]
+    fn compose_groups() {
+        <Custom as CommandFactory>::command().debug_assert();
+
+        let custom = Custom::parse_from([
+            "custom",
+            "--manifest-path",
+            "a/Cargo.toml",
+            "-p",
+            "foo",
+            "--workspace",
+        ]);
+        let mut cmd = Command::new("cargo");
+        custom.manifest_options.apply(&mut cmd);
+        custom.package_selection.apply(&mut cmd);
+        let args: Vec<_> = cmd.get_args().map(|a| a.to_owned()).collect();
+        assert_eq!(
+            args,
+            [
+                "--manifest-path",
+                "a/Cargo.toml",
+                "--package",
+                "foo",
+                "--workspace"
+            ]
+        );
+    }
+
+    #[test]
+    fn top_level_before_subcommand() {
+        let build = Build::parse_from([
+            "build",
+            "--release",
+            "-C",
+            "foo",
+            "--lockfile-path",
+            "x.lock",
+        ]);
+        let args: Vec<_> = build.command().get_args().map(|a| a.to_owned()).collect();
+        assert_eq!(
+            args,
+            [
+                "-C",
+                "foo",
+                "build",
+                "--lockfile-path",
+                "x.lock",
+                "-Z",
+                "unstable-options",
+                "--release"
+            ]
+        );
+    }
+
+    #[test]
+    fn provenance() {
+        let matches = <Build as CommandFactory>::command().get_matches_from([
+            "build",
+            "--target",
+            "x86_64-unknown-linux-gnu",
+            "--release",
+        ]);
+        let mut build = Build::from_arg_matches(&matches).unwrap();
+        build.provenance = Provenance::from_matches(&matches);
+        assert_eq!(
+            build.provenance.get("target"),
+            Some(OptionSource::CommandLine)
+        );
+        assert_eq!(build.provenance.get("lib"), Some(OptionSource::Default));
+        assert_eq!(build.provenance.get("jobs"), None);
+
+        let args = |build: &Build| -> Vec<String> {
+            build
+                .command()
+                .get_args()
+                .map(|a| a.to_string_lossy().into_owned())
+                .collect()
         };
-        let mut cmd = Command::new(cargo);
-        cmd.env_remove("CARGO");
-        cmd
+        assert!(args(&build).contains(&"--target".to_string()));
+
+        // cargo reads `CARGO_BUILD_TARGET` itself, unless it needs normalizing
+        build.provenance.insert("target", OptionSource::Environment);
+        assert_eq!(args(&build), ["build", "--release"]);
+        build.target = vec!["x86_64-unknown-linux-gnu.2.17".to_string()];
+        assert_eq!(
+            args(&build),
+            ["build", "--target", "x86_64-unknown-linux-gnu", "--release"]
+        );
+
+        // `-v` overrides `CARGO_TERM_QUIET`, cargo rejects `--quiet -v`
+        let mut build = Build::parse_from(["build", "-q", "-v"]);
+        assert_eq!(args(&build), ["build", "--quiet", "-v"]);
+        build.provenance.insert("quiet", OptionSource::Environment);
+        assert_eq!(args(&build), ["build", "-v"]);
+    }
+
+    #[test]
+    fn jobs() {
+        let build = Build::parse_from(["build", "-j", "-1"]);
+        assert_eq!(build.jobs.as_deref(), Some("-1"));
+        let args: Vec<_> = build.command().get_args().map(|a| a.to_owned()).collect();
+        assert_eq!(args, ["build", "--jobs", "-1"]);
+        assert_eq!(parse_jobs("default").as_deref(), Ok("default"));
+        assert!(parse_jobs("0").is_err());
+        assert!(parse_jobs("many").is_err());
+    }
+
+    #[cfg(feature = "env")]
+    #[test]
+    fn env_bindings() {
+        let cmd = <crate::Install as CommandFactory>::command();
+        let env = |id: &str| {
+            cmd.get_arguments()
+                .find(|arg| arg.get_id() == id)
+                .and_then(|arg| arg.get_env())
+                .and_then(|env| env.to_str())
+        };
+        assert_eq!(env("jobs"), Some("CARGO_BUILD_JOBS"));
+        assert_eq!(env("target_dir"), Some("CARGO_TARGET_DIR"));
+        assert_eq!(env("verbose"), Some("CARGO_TERM_VERBOSE"));
+        assert_eq!(env("offline"), Some("CARGO_NET_OFFLINE"));
+        assert_eq!(env("root"), Some("CARGO_INSTALL_ROOT"));
+
+        assert_eq!(parse_verbose("true"), Ok(1));
+        assert_eq!(parse_verbose("false"), Ok(0));
+        assert_eq!(parse_verbose("2"), Ok(2));
+        assert!(parse_verbose("yes").is_err());
+
+        let mut build = Build::parse_from(["build", "-v", "--color", "never", "-j", "2"]);
+        build
+            .provenance
+            .insert("verbose", OptionSource::Environment);
+        build.provenance.insert("color", OptionSource::Environment);
+        let args: Vec<_> = build.command().get_args().map(|a| a.to_owned()).collect();
+        assert_eq!(args, ["build", "--jobs", "2"]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn run_with_secret() {
+        let check_stdin = |expected: &str| {
+            let mut cmd = Command::new("sh");
+            cmd.arg("-c")
+                .arg(format!("read token && test \"$token\" = {}", expected));
+            cmd.env("CARGO_REGISTRY_TOKEN", "s3cr3t");
+            super::run_with_secret(&mut cmd, Some("s3cr3t"))
+        };
+        check_stdin("s3cr3t").unwrap();
+        let err = check_stdin("other").unwrap_err();
+        assert!(err.to_string().starts_with("`sh -c read token"));
+        assert!(!err.to_string().contains("CARGO_REGISTRY_TOKEN"));
     }
 }
@@ -0,0 +1,461 @@
+use std::fmt;
+use std::io;
+use std::ops::{Deref, DerefMut};
+use std::path::PathBuf;
+use std::process::Command;
+use std::str::FromStr;
+
+use clap::{Parser, ValueEnum};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::common::{self, CommonOptions, GlobalOptions};
+use crate::toml::{literal_string_end, parse_literal_string, parse_string, string_end};
+
+/// Display format of `cargo config get`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, ValueEnum)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
+pub enum ConfigFormat {
+    Toml,
+    Json,
+    JsonValue,
+}
+
+impl ConfigFormat {
+    /// Value passed to `--format`
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ConfigFormat::Toml => "toml",
+            ConfigFormat::Json => "json",
+            ConfigFormat::JsonValue => "json-value",
+        }
+    }
+}
+
+impl fmt::Display for ConfigFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Inspect configuration values (unstable)
+#[derive(Clone, Debug, Default, Parser)]
+#[command(
+    display_order = 1,
+    after_help = "Run `cargo help config` for more detailed information."
+)]
+#[group(skip)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct Config {
+    #[command(flatten)]
+    #[cfg_attr(feature = "serde", serde(flatten))]
+    pub global_options: GlobalOptions,
+
+    /// Display format [default: toml]
+    #[arg(long, value_name = "format")]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub format: Option<ConfigFormat>,
+
+    /// Display where the config value is defined
+    #[arg(long)]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub show_origin: bool,
+
+    /// Whether or not to merge config values [default: yes]
+    #[arg(long, value_name = "merged", value_parser = ["yes", "no"])]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub merged: Option<String>,
+
+    /// The config key to display
+    #[arg(value_name = "key")]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub key: Option<String>,
+}
+
+impl Config {
+    /// Build a `cargo config get` command
+    pub fn command(&self) -> Command {
+        let mut cmd = CommonOptions::cargo_command();
+        self.global_options.apply_top_level(&mut cmd);
+        cmd.arg("config").arg("get");
+        self.global_options
+            .apply_with_unstable(&mut cmd, &self.unstable_options());
+        if let Some(format) = self.format {
+            cmd.arg("--format").arg(format.as_str());
+        }
+        if self.show_origin {
+            cmd.arg("--show-origin");
+        }
+        if let Some(merged) = self.merged.as_ref() {
+            cmd.arg("--merged").arg(merged);
+        }
+        if let Some(key) = self.key.as_ref() {
+            cmd.arg(key);
+        }
+        cmd
+    }
+
+    /// Unstable options in use, cargo only takes them with `-Z unstable-options`
+    ///
+    /// `cargo config` itself is unstable, so this is never empty.
+    pub fn unstable_options(&self) -> Vec<&'static str> {
+        let mut options = vec!["cargo config"];
+        options.extend(self.global_options.unstable_options());
+        options
+    }
+
+    /// Whether the options only work on a nightly cargo, always true
+    pub fn requires_nightly(&self) -> bool {
+        self.global_options
+            .requires_nightly_for(&self.unstable_options())
+    }
+
+    /// Run `cargo config get` and return the values with their origin
+    ///
+    /// Forces `--format toml --show-origin` and `-Z unstable-options`,
+    /// so this needs a nightly cargo.
+    pub fn values(&self) -> io::Result<ConfigValues> {
+        let mut config = self.clone();
+        config.format = Some(ConfigFormat::Toml);
+        config.show_origin = true;
+        config.enable_unstable_options();
+        let stdout = common::capture_stdout(&mut config.command())?;
+        stdout.parse()
+    }
+}
+
+impl Deref for Config {
+    type Target = GlobalOptions;
+
+    fn deref(&self) -> &Self::Target {
+        &self.global_options
+    }
+}
+
+impl DerefMut for Config {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.global_options
+    }
+}
+
+/// Where a config value is defined
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum ConfigOrigin {
+    /// A config file, including files passed with `--config <PATH>`
+    File(PathBuf),
+    /// An environment variable such as `CARGO_BUILD_JOBS`
+    Environment(String),
+    /// A `--config KEY=VALUE` command line option
+    Cli,
+}
+
+impl FromStr for ConfigOrigin {
+    type Err = io::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s == "--config cli option" {
+            Ok(ConfigOrigin::Cli)
+        } else if let Some(var) = s
+            .strip_prefix("environment variable `")
+            .and_then(|s| s.strip_suffix('`'))
+        {
+            Ok(ConfigOrigin::Environment(var.to_string()))
+        } else {
+            Ok(ConfigOrigin::File(PathBuf::from(s)))
+        }
+    }
+}
+
+impl fmt::Display for ConfigOrigin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigOrigin::File(path) => write!(f, "{}", path.display()),
+            ConfigOrigin::Environment(var) => write!(f, "environment variable `{}`", var),
+            ConfigOrigin::Cli => f.write_str("--config cli option"),
+        }
+    }
+}
+
+/// A config value as printed by `cargo config get`
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+#[cfg_attr(feature = "serde", serde(untagged))]
+pub enum ConfigValue {
+    Boolean(bool),
+    Integer(i64),
+    String(String),
+    /// Array elements may each come from a different config file
+    List(Vec<ConfigListItem>),
+}
+
+impl ConfigValue {
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            ConfigValue::Boolean(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    pub fn as_integer(&self) -> Option<i64> {
+        match self {
+            ConfigValue::Integer(i) => Some(*i),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            ConfigValue::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// Array elements, without their origin
+    pub fn as_list(&self) -> Option<Vec<&str>> {
+        match self {
+            ConfigValue::List(items) => Some(items.iter().map(|i| i.value.as_str()).collect()),
+            _ => None,
+        }
+    }
+}
+
+/// An element of an array config value
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct ConfigListItem {
+    pub value: String,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub origin: Option<ConfigOrigin>,
+}
+
+/// A single `key = value` line
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct ConfigEntry {
+    /// Dotted key as printed by cargo, e.g. `target.x86_64-unknown-linux-gnu.linker`
+    pub key: String,
+    pub value: ConfigValue,
+    /// Where the value is defined, `None` for arrays whose elements carry
+    /// their own origin or when `--show-origin` was not passed
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub origin: Option<ConfigOrigin>,
+}
+
+/// Parsed `cargo config get --format toml` output
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct ConfigValues {
+    pub entries: Vec<ConfigEntry>,
+}
+
+impl ConfigValues {
+    /// Find an entry by its dotted key
+    pub fn get(&self, key: &str) -> Option<&ConfigEntry> {
+        self.entries.iter().find(|entry| entry.key == key)
+    }
+}
+
+impl FromStr for ConfigValues {
+    type Err = io::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut entries = Vec::new();
+        let mut lines = s.lines();
+        while let Some(line) = lines.next() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (key, rest) = split_key(line)
+                .ok_or_else(|| invalid_data(format!("expected `key = value`, got `{}`", line)))?;
+            let key = key.to_string();
+            if rest == "[" {
+                let mut items = Vec::new();
+                for line in lines.by_ref() {
+                    let line = line.trim();
+                    if line == "]" {
+                        break;
+                    }
+                    let (value, rest) = parse_scalar(line)?;
+                    let value = match value {
+                        ConfigValue::String(value) => value,
+                        _ => return Err(invalid_data(format!("unexpected array item `{}`", line))),
+                    };
+                    let rest = rest.strip_prefix(',').unwrap_or(rest).trim_start();
+                    items.push(ConfigListItem {
+                        value,
+                        origin: parse_origin(rest)?,
+                    });
+                }
+                entries.push(ConfigEntry {
+                    key,
+                    value: ConfigValue::List(items),
+                    origin: None,
+                });
+            } else if let Some(array) = rest.strip_prefix('[') {
+                let mut items = Vec::new();
+                let mut rest = array.trim_start();
+                loop {
+                    if let Some(tail) = rest.strip_prefix(']') {
+                        rest = tail.trim_start();
+                        break;
+                    }
+                    let (value, tail) = match parse_scalar(rest)? {
+                        (ConfigValue::String(value), tail) => (value, tail),
+                        _ => return Err(invalid_data(format!("unexpected array `{}`", line))),
+                    };
+                    items.push(ConfigListItem {
+                        value,
+                        origin: None,
+                    });
+                    rest = tail.strip_prefix(',').unwrap_or(tail).trim_start();
+                }
+                entries.push(ConfigEntry {
+                    key,
+                    value: ConfigValue::List(items),
+                    origin: parse_origin(rest)?,
+                });
+            } else {
+                let (value, rest) = parse_scalar(rest)?;
+                entries.push(ConfigEntry {
+                    key,
+                    value,
+                    origin: parse_origin(rest)?,
+                });
+            }
+        }
+        Ok(Self { entries })
+    }
+}
+
+/// Split `key = rest`, skipping over quoted key segments
+fn split_key(line: &str) -> Option<(&str, &str)> {
+    let mut i = 0;
+    while i < line.len() {
+        let rest = &line[i..];
+        if rest.starts_with('"') {
+            i += string_end(rest)?;
+        } else if let Some(value) = rest.strip_prefix(" = ") {
+            return Some((&line[..i], value.trim_start()));
+        } else {
+            i += rest.chars().next()?.len_utf8();
+        }
+    }
+    None
+}
+
+/// Parse a leading string, integer or boolean, returning what follows it
+fn parse_scalar(s: &str) -> io::Result<(ConfigValue, &str)> {
+    let unterminated = || invalid_data(format!("unterminated string in `{}`", s));
+    if s.starts_with('"') {
+        let end = string_end(s).ok_or_else(unterminated)?;
+        let value = parse_string(&s[..end])?;
+        return Ok((ConfigValue::String(value), s[end..].trim_start()));
+    }
+    if s.starts_with('\'') {
+        let end = literal_string_end(s).ok_or_else(unterminated)?;
+        let value = parse_literal_string(&s[..end])?;
+        return Ok((ConfigValue::String(value), s[end..].trim_start()));
+    }
+    let end = s.find([' ', ',', ']']).unwrap_or(s.len());
+    let value = match &s[..end] {
+        "true" => ConfigValue::Boolean(true),
+        "false" => ConfigValue::Boolean(false),
+        token => token
+            .replace('_', "")
+            .parse()
+            .map(ConfigValue::Integer)
+            .map_err(|_| invalid_data(format!("unsupported config value `{}`", token)))?,
+    };
+    Ok((value, s[end..].trim_start()))
+}
+
+/// Parse a trailing `# origin` comment
+fn parse_origin(s: &str) -> io::Result<Option<ConfigOrigin>> {
+    match s.strip_prefix('#') {
+        Some(origin) => origin.trim().parse().map(Some),
+        None if s.is_empty() => Ok(None),
+        None => Err(invalid_data(format!("unexpected trailing `{}`", s))),
+    }
+}
+
+fn invalid_data(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Config, ConfigOrigin, ConfigValue, ConfigValues};
+    use clap::CommandFactory;
+    use std::path::PathBuf;
+
+    #[test]
+    fn verify_cli() {
+        <Config as CommandFactory>::command().debug_assert()
+    }
+
+    #[test]
+    fn parse_values_with_origin() {
+        let stdout = r#"build.jobs = 4 # /work/.cargo/config.toml
+build.rustflags = [
+    "-C", # /work/.cargo/config.toml
+    "opt-level=1", # /home/user/.cargo/config.toml
+]
+build.target-dir = "x" # --config cli option
+net.offline = false # environment variable `CARGO_NET_OFFLINE`
+target.x86_64-unknown-linux-gnu.linker = 'C:\clang' # /work/.cargo/config.toml
+target."cfg(unix)".runner = ["valgrind", "--quiet"]
+# The following environment variables may affect the loaded values.
+# CARGO_HOME=/home/user/.cargo
+"#;
+        let values: ConfigValues = stdout.parse().unwrap();
+        assert_eq!(values.entries.len(), 6);
+        let jobs = values.get("build.jobs").unwrap();
+        assert_eq!(jobs.value.as_integer(), Some(4));
+        assert_eq!(
+            jobs.origin,
+            Some(ConfigOrigin::File(PathBuf::from(
+                "/work/.cargo/config.toml"
+            )))
+        );
+        let rustflags = &values.get("build.rustflags").unwrap().value;
+        assert_eq!(rustflags.as_list(), Some(vec!["-C", "opt-level=1"]));
+        match rustflags {
+            ConfigValue::List(items) => assert_eq!(
+                items[1].origin.as_ref().unwrap().to_string(),
+                "/home/user/.cargo/config.toml"
+            ),
+            _ => unreachable!(),
+        }
+        assert_eq!(
+            values.get("build.target-dir").unwrap().origin,
+            Some(ConfigOrigin::Cli)
+        );
+        let offline = values.get("net.offline").unwrap();
+        assert_eq!(offline.value.as_bool(), Some(false));
+        assert_eq!(
+            offline.origin,
+            Some(ConfigOrigin::Environment("CARGO_NET_OFFLINE".to_string()))
+        );
+        assert_eq!(
+            values
+                .get("target.x86_64-unknown-linux-gnu.linker")
+                .unwrap()
+                .value
+                .as_str(),
+            Some(r"C:\clang")
+        );
+        assert_eq!(
+            values
+                .get(r#"target."cfg(unix)".runner"#)
+                .unwrap()
+                .value
+                .as_list(),
+            Some(vec!["valgrind", "--quiet"])
+        );
+    }
+}
@@ -0,0 +1,318 @@
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::toml::{
+    literal_string_end, parse_dotted_key, parse_literal_string, parse_string, parse_string_array,
+    render_dotted_key, render_string, string_end,
+};
+
+/// A value of a `--config KEY=VALUE` override
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
+pub enum ConfigOverrideValue {
+    Boolean(bool),
+    Integer(i64),
+    String(String),
+    Array(Vec<String>),
+}
+
+impl fmt::Display for ConfigOverrideValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigOverrideValue::Boolean(b) => write!(f, "{}", b),
+            ConfigOverrideValue::Integer(i) => write!(f, "{}", i),
+            ConfigOverrideValue::String(s) => f.write_str(&render_string(s)),
+            ConfigOverrideValue::Array(items) => {
+                let items: Vec<_> = items.iter().map(|item| render_string(item)).collect();
+                write!(f, "[{}]", items.join(", "))
+            }
+        }
+    }
+}
+
+impl FromStr for ConfigOverrideValue {
+    type Err = io::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let value = match s {
+            "true" => ConfigOverrideValue::Boolean(true),
+            "false" => ConfigOverrideValue::Boolean(false),
+            _ if s.starts_with('"') && string_end(s) == Some(s.len()) => {
+                ConfigOverrideValue::String(parse_string(s)?)
+            }
+            _ if s.starts_with('\'') && literal_string_end(s) == Some(s.len()) => {
+                ConfigOverrideValue::String(parse_literal_string(s)?)
+            }
+            _ if s.starts_with('[') => ConfigOverrideValue::Array(parse_string_array(s)?),
+            _ => s
+                .replace('_', "")
+                .parse()
+                .map(ConfigOverrideValue::Integer)
+                .map_err(|_| invalid_data(format!("unsupported config value `{}`", s)))?,
+        };
+        Ok(value)
+    }
+}
+
+impl From<bool> for ConfigOverrideValue {
+    fn from(b: bool) -> Self {
+        ConfigOverrideValue::Boolean(b)
+    }
+}
+
+impl From<i64> for ConfigOverrideValue {
+    fn from(i: i64) -> Self {
+        ConfigOverrideValue::Integer(i)
+    }
+}
+
+impl From<&str> for ConfigOverrideValue {
+    fn from(s: &str) -> Self {
+        ConfigOverrideValue::String(s.to_string())
+    }
+}
+
+impl From<String> for ConfigOverrideValue {
+    fn from(s: String) -> Self {
+        ConfigOverrideValue::String(s)
+    }
+}
+
+impl From<Vec<String>> for ConfigOverrideValue {
+    fn from(items: Vec<String>) -> Self {
+        ConfigOverrideValue::Array(items)
+    }
+}
+
+/// A typed `--config` argument, rendered with the TOML quoting cargo expects
+///
+/// ```
+/// use cargo_options::ConfigOverride;
+///
+/// let linker = ConfigOverride::linker("x86_64-unknown-linux-gnu", "clang");
+/// assert_eq!(
+///     linker.to_string(),
+///     r#"target.x86_64-unknown-linux-gnu.linker="clang""#
+/// );
+/// assert_eq!(linker.to_string().parse::<ConfigOverride>().unwrap(), linker);
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
+pub enum ConfigOverride {
+    /// A `KEY=VALUE` pair, with the dotted key split into its parts
+    Value {
+        key: Vec<String>,
+        value: ConfigOverrideValue,
+    },
+    /// Path to an extra config file
+    File(PathBuf),
+}
+
+impl ConfigOverride {
+    /// Set `key`, given as its parts, e.g. `["build", "jobs"]`, to `value`
+    pub fn new<I, S>(key: I, value: impl Into<ConfigOverrideValue>) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        ConfigOverride::Value {
+            key: key.into_iter().map(Into::into).collect(),
+            value: value.into(),
+        }
+    }
+
+    /// Load an extra config file, merged like `--config KEY=VALUE` entries
+    pub fn file(path: impl Into<PathBuf>) -> Self {
+        ConfigOverride::File(path.into())
+    }
+
+    /// `profile.<profile>.<setting>`, e.g. `profile.release.lto`
+    pub fn profile(profile: &str, setting: &str, value: impl Into<ConfigOverrideValue>) -> Self {
+        Self::new(["profile", profile, setting], value)
+    }
+
+    /// `build.rustflags`
+    pub fn rustflags<I, S>(flags: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self::new(["build", "rustflags"], string_array(flags))
+    }
+
+    /// `target.<triple>.linker`, `triple` may also be a `cfg(...)` expression
+    pub fn linker(triple: &str, linker: impl Into<String>) -> Self {
+        Self::new(["target", triple, "linker"], linker.into())
+    }
+
+    /// `target.<triple>.runner`, the program followed by its arguments
+    pub fn runner<I, S>(triple: &str, runner: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self::new(["target", triple, "runner"], string_array(runner))
+    }
+
+    /// `net.offline`
+    pub fn offline(offline: bool) -> Self {
+        Self::new(["net", "offline"], offline)
+    }
+
+    /// `source.<source>.replace-with`
+    pub fn replace_with(source: &str, replacement: &str) -> Self {
+        Self::new(["source", source, "replace-with"], replacement)
+    }
+
+    /// `env.<name>`, an environment variable set for build scripts, rustc and
+    /// `cargo run`/`cargo test`
+    pub fn env(name: &str, value: impl Into<String>) -> Self {
+        Self::new(["env", name], value.into())
+    }
+
+    /// Dotted key parts, `None` for config files
+    pub fn key(&self) -> Option<&[String]> {
+        match self {
+            ConfigOverride::Value { key, .. } => Some(key),
+            ConfigOverride::File(_) => None,
+        }
+    }
+
+    /// Value, `None` for config files
+    pub fn value(&self) -> Option<&ConfigOverrideValue> {
+        match self {
+            ConfigOverride::Value { value, .. } => Some(value),
+            ConfigOverride::File(_) => None,
+        }
+    }
+}
+
+impl fmt::Display for ConfigOverride {
+    /// Render the `--config` argument, config file paths that aren't
+    /// valid UTF-8 are converted lossily
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigOverride::Value { key, value } => {
+                write!(f, "{}={}", render_dotted_key(key), value)
+            }
+            ConfigOverride::File(path) => write!(f, "{}", path.display()),
+        }
+    }
+}
+
+impl FromStr for ConfigOverride {
+    type Err = io::Error;
+
+    /// Parse a `--config` argument
+    ///
+    /// Arguments without an `=` are taken as config file paths. cargo checks
+    /// whether the file exists first, which isn't done here.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if !s.contains('=') {
+            return Ok(ConfigOverride::File(PathBuf::from(s)));
+        }
+        let (key, value) = parse_dotted_key(s, '=')?;
+        Ok(ConfigOverride::Value {
+            key,
+            value: value.parse()?,
+        })
+    }
+}
+
+impl From<ConfigOverride> for String {
+    fn from(config: ConfigOverride) -> Self {
+        config.to_string()
+    }
+}
+
+fn string_array<I, S>(items: I) -> ConfigOverrideValue
+where
+    I: IntoIterator<Item = S>,
+    S: Into<String>,
+{
+    ConfigOverrideValue::Array(items.into_iter().map(Into::into).collect())
+}
+
+fn invalid_data(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{ConfigOverride, ConfigOverrideValue};
+    use std::path::Path;
+
+    #[test]
+    fn render() {
+        let cases = [
+            (
+                ConfigOverride::profile("release", "lto", "fat"),
+                r#"profile.release.lto="fat""#,
+            ),
+            (
+                ConfigOverride::profile("dev", "codegen-units", 16),
+                "profile.dev.codegen-units=16",
+            ),
+            (
+                ConfigOverride::rustflags(["-C", "link-arg=-Wl,--as-needed"]),
+                r#"build.rustflags=["-C", "link-arg=-Wl,--as-needed"]"#,
+            ),
+            (
+                ConfigOverride::linker("cfg(target_os = \"linux\")", "C:\\cc.exe"),
+                r#"target."cfg(target_os = \"linux\")".linker="C:\\cc.exe""#,
+            ),
+            (
+                ConfigOverride::runner("wasm32-wasip1", ["wasmtime", "--dir=."]),
+                r#"target.wasm32-wasip1.runner=["wasmtime", "--dir=."]"#,
+            ),
+            (ConfigOverride::offline(true), "net.offline=true"),
+            (
+                ConfigOverride::replace_with("crates-io", "vendored-sources"),
+                r#"source.crates-io.replace-with="vendored-sources""#,
+            ),
+            (
+                ConfigOverride::env("GREETING", "hi\n"),
+                r#"env.GREETING="hi\n""#,
+            ),
+            (ConfigOverride::file("ci.toml"), "ci.toml"),
+        ];
+        for (config, expected) in cases {
+            assert_eq!(config.to_string(), expected);
+            assert_eq!(expected.parse::<ConfigOverride>().unwrap(), config);
+        }
+    }
+
+    #[test]
+    fn parse() {
+        let config: ConfigOverride = "target . 'x86_64-pc-windows-msvc' . linker = 'lld-link'"
+            .parse()
+            .unwrap();
+        assert_eq!(
+            config.key().unwrap(),
+            ["target", "x86_64-pc-windows-msvc", "linker"]
+        );
+        assert_eq!(
+            config.value(),
+            Some(&ConfigOverrideValue::String("lld-link".to_string()))
+        );
+        assert_eq!(
+            "build.jobs=1_000".parse::<ConfigOverride>().unwrap(),
+            ConfigOverride::new(["build", "jobs"], 1000)
+        );
+        assert_eq!(
+            "../.cargo/config.toml".parse::<ConfigOverride>().unwrap(),
+            ConfigOverride::File(Path::new("../.cargo/config.toml").to_path_buf())
+        );
+        assert!("build.jobs=1.5".parse::<ConfigOverride>().is_err());
+        assert!("build..jobs=1".parse::<ConfigOverride>().is_err());
+        assert!(r#"env.A="unterminated"#.parse::<ConfigOverride>().is_err());
+    }
+}
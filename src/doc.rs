@@ -1,87 +1,28 @@
+use std::io;
 use std::ops::{Deref, DerefMut};
-use std::path::PathBuf;
 use std::process::Command;
 
-use clap::{ArgAction, Parser};
+use clap::Parser;
 
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
-use crate::common::CommonOptions;
-use crate::heading;
+use crate::common::{
+    CommonOptions, CompilationOptions, LibBinExampleSelection, ManifestOptions, PackageSelection,
+};
+use crate::CargoVersion;
 
 /// `cargo doc` options
 #[derive(Clone, Debug, Default, Parser)]
 #[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
 pub struct DocOptions {
-    /// Package to document
-    #[arg(
-        short = 'p',
-        long = "package",
-        value_name = "SPEC",
-        action = ArgAction::Append,
-        num_args=0..=1,
-        help_heading = heading::PACKAGE_SELECTION,
-    )]
-    #[cfg_attr(feature = "serde", serde(default))]
-    pub packages: Vec<String>,
-
-    /// Document all packages in the workspace
-    #[arg(long, help_heading = heading::PACKAGE_SELECTION)]
-    #[cfg_attr(feature = "serde", serde(default))]
-    pub workspace: bool,
-
-    /// Exclude packages from the build
-    #[arg(
-        long,
-        value_name = "SPEC",
-        action = ArgAction::Append,
-        help_heading = heading::PACKAGE_SELECTION,
-    )]
-    #[cfg_attr(feature = "serde", serde(default))]
-    pub exclude: Vec<String>,
-
-    /// Alias for --workspace (deprecated)
-    #[arg(long, help_heading = heading::PACKAGE_SELECTION,)]
-    #[cfg_attr(feature = "serde", serde(default))]
-    pub all: bool,
-
-    /// Document only this package's library
-    #[arg(long, help_heading = heading::TARGET_SELECTION)]
-    #[cfg_attr(feature = "serde", serde(default))]
-    pub lib: bool,
-
-    /// Document only the specified binary
-    #[arg(
-        long,
-        value_name = "NAME",
-        action = ArgAction::Append,
-        num_args=0..=1,
-        help_heading = heading::TARGET_SELECTION,
-    )]
-    #[cfg_attr(feature = "serde", serde(default))]
-    pub bin: Vec<String>,
-
-    /// Document all binaries
-    #[arg(long, help_heading = heading::TARGET_SELECTION)]
-    #[cfg_attr(feature = "serde", serde(default))]
-    pub bins: bool,
-
-    /// Document only the specified example
-    #[arg(
-        long,
-        value_name = "NAME",
-        action = ArgAction::Append,
-        num_args=0..=1,
-        help_heading = heading::TARGET_SELECTION,
-    )]
-    #[cfg_attr(feature = "serde", serde(default))]
-    pub example: Vec<String>,
+    #[command(flatten)]
+    #[cfg_attr(feature = "serde", serde(flatten))]
+    pub package_selection: PackageSelection,
 
-    /// Document all examples
-    #[arg(long, help_heading = heading::TARGET_SELECTION)]
-    #[cfg_attr(feature = "serde", serde(default))]
-    pub examples: bool,
+    #[command(flatten)]
+    #[cfg_attr(feature = "serde", serde(flatten))]
+    pub target_selection: LibBinExampleSelection,
 
     /// Don't build documentation for dependencies
     #[arg(long)]
@@ -101,33 +42,8 @@ pub struct DocOptions {
 
 impl DocOptions {
     pub fn apply(&self, cmd: &mut Command) {
-        for pkg in &self.packages {
-            cmd.arg("--package").arg(pkg);
-        }
-        if self.workspace {
-            cmd.arg("--workspace");
-        }
-        for item in &self.exclude {
-            cmd.arg("--exclude").arg(item);
-        }
-        if self.all {
-            cmd.arg("--all");
-        }
-        if self.lib {
-            cmd.arg("--lib");
-        }
-        for bin in &self.bin {
-            cmd.arg("--bin").arg(bin);
-        }
-        if self.bins {
-            cmd.arg("--bins");
-        }
-        for example in &self.example {
-            cmd.arg("--example").arg(example);
-        }
-        if self.examples {
-            cmd.arg("--examples");
-        }
+        self.package_selection.apply(cmd);
+        self.target_selection.apply(cmd);
         if self.no_deps {
             cmd.arg("--no-deps");
         }
@@ -157,51 +73,53 @@ pub struct Doc {
     #[cfg_attr(feature = "serde", serde(flatten))]
     pub doc: DocOptions,
 
-    /// Path to Cargo.toml
-    #[arg(long, value_name = "PATH", help_heading = heading::MANIFEST_OPTIONS)]
-    #[cfg_attr(feature = "serde", serde(default))]
-    pub manifest_path: Option<PathBuf>,
-
-    /// Build artifacts in release mode, with optimizations
-    #[arg(short = 'r', long, help_heading = heading::COMPILATION_OPTIONS)]
-    #[cfg_attr(feature = "serde", serde(default))]
-    pub release: bool,
-
-    /// Ignore `rust-version` specification in packages
-    #[arg(long)]
-    #[cfg_attr(feature = "serde", serde(default))]
-    pub ignore_rust_version: bool,
+    #[command(flatten)]
+    #[cfg_attr(feature = "serde", serde(flatten))]
+    pub compilation_options: CompilationOptions,
 
-    /// Output build graph in JSON (unstable)
-    #[arg(long, help_heading = heading::COMPILATION_OPTIONS)]
-    #[cfg_attr(feature = "serde", serde(default))]
-    pub unit_graph: bool,
+    #[command(flatten)]
+    #[cfg_attr(feature = "serde", serde(flatten))]
+    pub manifest_options: ManifestOptions,
 }
 
 impl Doc {
     /// Build a `cargo doc` command
     pub fn command(&self) -> Command {
         let mut cmd = CommonOptions::cargo_command();
+        self.common.global_options.apply_top_level(&mut cmd);
         cmd.arg("doc");
 
-        self.common.apply(&mut cmd);
+        self.common
+            .apply_with_unstable(&mut cmd, &self.unstable_options());
         self.doc.apply(&mut cmd);
 
-        if let Some(path) = self.manifest_path.as_ref() {
-            cmd.arg("--manifest-path").arg(path);
-        }
-        if self.release {
-            cmd.arg("--release");
-        }
-        if self.ignore_rust_version {
-            cmd.arg("--ignore-rust-version");
-        }
-        if self.unit_graph {
-            cmd.arg("--unit-graph");
-        }
+        self.compilation_options.apply(&mut cmd);
+        self.manifest_options.apply(&mut cmd);
 
         cmd
     }
+
+    /// Build a `cargo doc` command that `version` understands
+    ///
+    /// `-Z unstable-options` is added when an option is unstable on `version`
+    /// and options `version` doesn't have (anymore) are an error.
+    pub fn command_for(&self, version: &CargoVersion) -> io::Result<Command> {
+        let mut doc = self.clone();
+        doc.common.adapt_to_version(version)?;
+        Ok(doc.command())
+    }
+
+    /// Unstable options in use, cargo only takes them with `-Z unstable-options`
+    pub fn unstable_options(&self) -> Vec<&'static str> {
+        let mut options = self.common.unstable_options();
+        options.extend(self.compilation_options.unstable_options());
+        options
+    }
+
+    /// Whether the options only work on a nightly cargo
+    pub fn requires_nightly(&self) -> bool {
+        self.common.requires_nightly_for(&self.unstable_options())
+    }
 }
 
 impl Deref for Doc {
@@ -221,10 +139,19 @@ impl DerefMut for Doc {
 #[cfg(test)]
 mod test {
     use super::Doc;
-    use clap::CommandFactory;
+    use clap::{CommandFactory, Parser};
 
     #[test]
     fn verify_cli() {
         <Doc as CommandFactory>::command().debug_assert()
     }
+
+    #[test]
+    fn target_selection() {
+        let doc = Doc::parse_from(["doc", "--lib", "--bin", "cli", "--no-deps"]);
+        assert!(doc.doc.target_selection.lib);
+        let args: Vec<_> = doc.command().get_args().map(|a| a.to_owned()).collect();
+        assert_eq!(args, ["doc", "--lib", "--bin", "cli", "--no-deps"]);
+        assert!(Doc::try_parse_from(["doc", "--tests"]).is_err());
+    }
 }
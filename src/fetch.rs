@@ -0,0 +1,126 @@
+use std::ops::{Deref, DerefMut};
+use std::path::PathBuf;
+use std::process::Command;
+
+use clap::{ArgAction, Parser};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::common::{self, CommonOptions, GlobalOptions};
+use crate::heading;
+use crate::Build;
+
+/// Fetch dependencies of a package from the network
+#[derive(Clone, Debug, Default, Parser)]
+#[command(
+    display_order = 1,
+    after_help = "Run `cargo help fetch` for more detailed information."
+)]
+#[group(skip)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct Fetch {
+    #[command(flatten)]
+    #[cfg_attr(feature = "serde", serde(flatten))]
+    pub global_options: GlobalOptions,
+
+    /// Fetch dependencies for the target triple
+    #[arg(
+        long,
+        value_name = "TRIPLE",
+        action = ArgAction::Append,
+        help_heading = heading::COMPILATION_OPTIONS,
+    )]
+    #[cfg_attr(feature = "env", arg(env = "CARGO_BUILD_TARGET"))]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub target: Vec<String>,
+
+    /// Path to Cargo.toml
+    #[arg(long, value_name = "PATH", help_heading = heading::MANIFEST_OPTIONS)]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub manifest_path: Option<PathBuf>,
+}
+
+impl Fetch {
+    /// Build a `cargo fetch` command
+    pub fn command(&self) -> Command {
+        let mut cmd = CommonOptions::cargo_command();
+        self.global_options.apply_top_level(&mut cmd);
+        cmd.arg("fetch");
+        self.global_options.apply(&mut cmd);
+        for target in common::target_args(&self.target, &self.provenance) {
+            cmd.arg("--target").arg(target);
+        }
+        if let Some(path) = self.manifest_path.as_ref() {
+            cmd.arg("--manifest-path").arg(path);
+        }
+        cmd
+    }
+}
+
+impl Deref for Fetch {
+    type Target = GlobalOptions;
+
+    fn deref(&self) -> &Self::Target {
+        &self.global_options
+    }
+}
+
+impl DerefMut for Fetch {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.global_options
+    }
+}
+
+impl From<&Build> for Fetch {
+    /// Fetch everything `build` needs, so it can later run with `--offline`
+    fn from(build: &Build) -> Self {
+        Self {
+            global_options: GlobalOptions {
+                frozen: false,
+                locked: build.locked || build.frozen,
+                offline: false,
+                ..build.global_options.clone()
+            },
+            target: build.target.clone(),
+            manifest_path: build.manifest_options.manifest_path.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Fetch;
+    use crate::Build;
+    use clap::{CommandFactory, Parser};
+
+    #[test]
+    fn verify_cli() {
+        <Fetch as CommandFactory>::command().debug_assert()
+    }
+
+    #[test]
+    fn fetch_for_build() {
+        let build = Build::parse_from([
+            "build",
+            "--frozen",
+            "--target",
+            "x86_64-unknown-linux-gnu.2.17",
+            "--manifest-path",
+            "foo/Cargo.toml",
+        ]);
+        let fetch = Fetch::from(&build);
+        let args: Vec<_> = fetch.command().get_args().map(|a| a.to_owned()).collect();
+        assert_eq!(
+            args,
+            [
+                "fetch",
+                "--locked",
+                "--target",
+                "x86_64-unknown-linux-gnu",
+                "--manifest-path",
+                "foo/Cargo.toml"
+            ]
+        );
+    }
+}
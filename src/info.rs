@@ -0,0 +1,266 @@
+use std::io;
+use std::ops::{Deref, DerefMut};
+use std::process::Command;
+use std::str::FromStr;
+
+use clap::Parser;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::common::{self, CommonOptions, GlobalOptions};
+use crate::heading;
+
+/// Display information about a package
+#[derive(Clone, Debug, Default, Parser)]
+#[command(
+    display_order = 1,
+    after_help = "Run `cargo help info` for more detailed information."
+)]
+#[group(skip)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct Info {
+    #[command(flatten)]
+    #[cfg_attr(feature = "serde", serde(flatten))]
+    pub global_options: GlobalOptions,
+
+    /// Registry index URL to search packages in
+    #[arg(long, value_name = "INDEX", conflicts_with = "registry")]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub index: Option<String>,
+
+    /// Registry to search packages in
+    #[arg(long, value_name = "REGISTRY")]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub registry: Option<String>,
+
+    /// Package to inspect
+    #[arg(value_name = "SPEC", help_heading = heading::PACKAGE_SELECTION)]
+    pub spec: String,
+}
+
+impl Info {
+    /// Build a `cargo info` command
+    pub fn command(&self) -> Command {
+        let mut cmd = CommonOptions::cargo_command();
+        self.global_options.apply_top_level(&mut cmd);
+        cmd.arg("info");
+        self.global_options.apply(&mut cmd);
+        if let Some(index) = self.index.as_ref() {
+            cmd.arg("--index").arg(index);
+        }
+        if let Some(registry) = self.registry.as_ref() {
+            cmd.arg("--registry").arg(registry);
+        }
+        cmd.arg(&self.spec);
+        cmd
+    }
+
+    /// Run `cargo info` and parse the package information
+    ///
+    /// Dependencies are only listed with `--verbose`.
+    pub fn package_info(&self) -> io::Result<PackageInfo> {
+        let stdout = common::capture_stdout(&mut self.command())?;
+        stdout.parse()
+    }
+}
+
+impl Deref for Info {
+    type Target = GlobalOptions;
+
+    fn deref(&self) -> &Self::Target {
+        &self.global_options
+    }
+}
+
+impl DerefMut for Info {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.global_options
+    }
+}
+
+/// Parsed `cargo info` output
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct PackageInfo {
+    pub name: String,
+    /// Selected version, e.g. the one locked in the current workspace
+    pub version: String,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub description: Option<String>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub keywords: Vec<String>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub license: Option<String>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub rust_version: Option<String>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub documentation: Option<String>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub homepage: Option<String>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub repository: Option<String>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub features: Vec<PackageFeature>,
+    /// Dependencies as printed by cargo, e.g. `serde_derive@=1.0.210 (optional)`
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub dependencies: Vec<String>,
+}
+
+/// A feature listed by `cargo info`
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct PackageFeature {
+    pub name: String,
+    /// Whether the feature is enabled by default
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub default: bool,
+    /// Features and dependencies this feature enables
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub enables: Vec<String>,
+}
+
+impl FromStr for PackageInfo {
+    type Err = io::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut lines = s.lines().skip_while(|line| line.trim().is_empty());
+        let header = lines
+            .next()
+            .ok_or_else(|| invalid_data("empty `cargo info` output".to_string()))?;
+        let mut words = header.split_whitespace();
+        let mut info = PackageInfo {
+            name: words.next().unwrap_or_default().to_string(),
+            keywords: words
+                .filter_map(|word| word.strip_prefix('#'))
+                .map(str::to_string)
+                .collect(),
+            ..Default::default()
+        };
+        let mut description = Vec::new();
+        let mut section = None;
+        for line in lines {
+            if let Some(item) = line.strip_prefix(' ') {
+                match section {
+                    Some("features") => {
+                        if let Some(feature) = parse_feature(item) {
+                            info.features.push(feature);
+                        }
+                    }
+                    Some("dependencies") => info.dependencies.push(item.trim().to_string()),
+                    _ => {}
+                }
+                continue;
+            }
+            let (key, value) = line.split_once(':').unwrap_or((line, ""));
+            let value = value.trim();
+            let value = || Some(value.to_string()).filter(|v| !v.is_empty() && v != "unknown");
+            section = None;
+            match key {
+                "version" => {
+                    info.version = line[key.len() + 1..]
+                        .split_whitespace()
+                        .next()
+                        .unwrap_or_default()
+                        .to_string();
+                }
+                "license" => info.license = value(),
+                "rust-version" => info.rust_version = value(),
+                "documentation" => info.documentation = value(),
+                "homepage" => info.homepage = value(),
+                "repository" => info.repository = value(),
+                "crates.io" => {}
+                "features" | "dependencies" => section = Some(key),
+                _ if info.version.is_empty() => description.push(line.trim()),
+                _ => {}
+            }
+        }
+        if info.version.is_empty() {
+            return Err(invalid_data(format!(
+                "no version in `cargo info` output for `{}`",
+                info.name
+            )));
+        }
+        info.description = Some(description.join("\n")).filter(|d| !d.is_empty());
+        Ok(info)
+    }
+}
+
+/// Parse a ` +default = [std]` style feature line
+fn parse_feature(line: &str) -> Option<PackageFeature> {
+    let (default, line) = match line.strip_prefix('+') {
+        Some(line) => (true, line),
+        None => (false, line.trim_start_matches('-')),
+    };
+    let (name, enables) = line.split_once('=').unwrap_or((line, ""));
+    let name = name.trim();
+    if name.is_empty() || name.starts_with("...") {
+        return None;
+    }
+    let enables = enables
+        .trim()
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(str::to_string)
+        .collect();
+    Some(PackageFeature {
+        name: name.to_string(),
+        default,
+        enables,
+    })
+}
+
+fn invalid_data(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Info, PackageInfo};
+    use clap::CommandFactory;
+
+    #[test]
+    fn verify_cli() {
+        <Info as CommandFactory>::command().debug_assert()
+    }
+
+    #[test]
+    fn parse_package_info() {
+        let stdout = "serde #serde #serialization #no_std
+A generic serialization/deserialization framework
+version: 1.0.210 (latest 1.0.215)
+license: MIT OR Apache-2.0
+rust-version: 1.31
+documentation: https://docs.rs/serde/1.0.210
+homepage: https://serde.rs
+repository: https://github.com/serde-rs/serde
+crates.io: https://crates.io/crates/serde/1.0.210
+features:
+ +default      = [std]
+  std          = []
+  alloc        = []
+  derive       = [serde_derive]
+  serde_derive = [dep:serde_derive]
+dependencies:
+  serde_derive@=1.0.210 (optional)
+";
+        let info: PackageInfo = stdout.parse().unwrap();
+        assert_eq!(info.name, "serde");
+        assert_eq!(info.keywords, ["serde", "serialization", "no_std"]);
+        assert_eq!(
+            info.description.as_deref(),
+            Some("A generic serialization/deserialization framework")
+        );
+        assert_eq!(info.version, "1.0.210");
+        assert_eq!(info.rust_version.as_deref(), Some("1.31"));
+        assert_eq!(info.features.len(), 5);
+        assert!(info.features[0].default);
+        assert_eq!(info.features[0].enables, ["std"]);
+        assert!(!info.features[3].default);
+        assert_eq!(info.features[3].name, "derive");
+        assert_eq!(info.dependencies, ["serde_derive@=1.0.210 (optional)"]);
+    }
+}
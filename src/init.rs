@@ -0,0 +1,70 @@
+use std::ops::{Deref, DerefMut};
+use std::path::PathBuf;
+use std::process::Command;
+
+use clap::Parser;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::new::NewOptions;
+use crate::CommonOptions;
+
+/// Create a new cargo package in an existing directory
+#[derive(Clone, Debug, Default, Parser)]
+#[command(
+    display_order = 1,
+    after_help = "Run `cargo help init` for more detailed information."
+)]
+#[group(skip)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct Init {
+    #[command(flatten)]
+    #[cfg_attr(feature = "serde", serde(flatten))]
+    pub new: NewOptions,
+
+    #[arg(value_name = "PATH")]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub path: Option<PathBuf>,
+}
+
+impl Init {
+    /// Build a `cargo init` command
+    pub fn command(&self) -> Command {
+        let mut cmd = CommonOptions::cargo_command();
+        self.new.global_options.apply_top_level(&mut cmd);
+        cmd.arg("init");
+
+        self.new.apply(&mut cmd);
+        if let Some(path) = self.path.as_ref() {
+            cmd.arg(path);
+        }
+
+        cmd
+    }
+}
+
+impl Deref for Init {
+    type Target = NewOptions;
+
+    fn deref(&self) -> &Self::Target {
+        &self.new
+    }
+}
+
+impl DerefMut for Init {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.new
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Init;
+    use clap::CommandFactory;
+
+    #[test]
+    fn verify_cli() {
+        <Init as CommandFactory>::command().debug_assert()
+    }
+}
@@ -1,5 +1,7 @@
+use std::fs;
+use std::io;
 use std::ops::{Deref, DerefMut};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 
 use clap::{ArgAction, Parser};
@@ -9,6 +11,8 @@ use serde::{Deserialize, Serialize};
 
 use crate::common::CommonOptions;
 use crate::heading;
+use crate::json;
+use crate::toml::{parse_string, parse_string_array, string_end};
 
 /// Install a Rust binary. Default location is $HOME/.cargo/bin
 #[derive(Clone, Debug, Default, Parser)]
@@ -75,6 +79,7 @@ pub struct Install {
 
     /// Directory to install packages into
     #[arg(long, value_name = "DIR")]
+    #[cfg_attr(feature = "env", arg(env = "CARGO_INSTALL_ROOT"))]
     #[cfg_attr(feature = "serde", serde(default))]
     pub root: Option<PathBuf>,
 
@@ -139,6 +144,7 @@ impl Install {
     /// Build a `cargo install` command
     pub fn command(&self) -> Command {
         let mut cmd = CommonOptions::cargo_command();
+        self.common.global_options.apply_top_level(&mut cmd);
         cmd.arg("install");
 
         self.common.apply(&mut cmd);
@@ -173,7 +179,11 @@ impl Install {
         if self.debug {
             cmd.arg("--debug");
         }
-        if let Some(root) = self.root.as_ref() {
+        if let Some(root) = self
+            .root
+            .as_ref()
+            .filter(|_| !self.provenance.is_from_env("root"))
+        {
             cmd.arg("--root").arg(root);
         }
         if let Some(index) = self.index.as_ref() {
@@ -214,13 +224,202 @@ impl DerefMut for Install {
     }
 }
 
+/// A package recorded in the install tracking files of an install root
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct InstalledPackage {
+    /// Package name
+    pub name: String,
+    /// Installed version
+    pub version: String,
+    /// Source id, e.g. `registry+https://github.com/rust-lang/crates.io-index`
+    pub source: String,
+    /// Installed binaries
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub bins: Vec<String>,
+    /// Version requirement given to `cargo install --version`
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub version_req: Option<String>,
+    /// Features the package was installed with
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub features: Vec<String>,
+    /// Whether `--all-features` was used
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub all_features: bool,
+    /// Whether `--no-default-features` was used
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub no_default_features: bool,
+    /// Profile the package was built with
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub profile: Option<String>,
+    /// Target triple the package was built for
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub target: Option<String>,
+    /// `rustc -vV` output of the compiler used
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub rustc: Option<String>,
+}
+
+impl InstalledPackage {
+    /// Read the packages installed under `root`, e.g. `$CARGO_HOME`
+    /// or the `--root` passed to `cargo install`.
+    ///
+    /// `.crates2.json` is read when it exists, it also records features,
+    /// profile, target and rustc version. Otherwise only name, version, source
+    /// and binaries are read from `.crates.toml`. A root without either file
+    /// has nothing installed.
+    pub fn read_all(root: impl AsRef<Path>) -> io::Result<Vec<InstalledPackage>> {
+        let root = root.as_ref();
+        let path = root.join(".crates2.json");
+        if path.is_file() {
+            return Self::parse_crates2_json(&fs::read_to_string(path)?);
+        }
+        let path = root.join(".crates.toml");
+        if path.is_file() {
+            return Self::parse_crates_toml(&fs::read_to_string(path)?);
+        }
+        Ok(Vec::new())
+    }
+
+    /// Parse the contents of a `.crates2.json` file
+    pub fn parse_crates2_json(s: &str) -> io::Result<Vec<InstalledPackage>> {
+        let invalid = |what: &str| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("unexpected .crates2.json contents: {}", what),
+            )
+        };
+        let crates2 = json::parse(s)?;
+        let installs = crates2
+            .get("installs")
+            .and_then(json::Value::as_object)
+            .ok_or_else(|| invalid("missing `installs`"))?;
+        let mut packages = Vec::new();
+        for (id, install) in installs {
+            let (name, version, source) = parse_package_id(id)?;
+            let string = |key| install.get(key).and_then(json::Value::as_str);
+            let strings = |key| -> io::Result<Vec<String>> {
+                match install.get(key) {
+                    None | Some(json::Value::Null) => Ok(Vec::new()),
+                    Some(value) => value
+                        .as_array()
+                        .and_then(|items| {
+                            items
+                                .iter()
+                                .map(|item| item.as_str().map(str::to_string))
+                                .collect()
+                        })
+                        .ok_or_else(|| invalid(&format!("`{}` is not a list of strings", key))),
+                }
+            };
+            let flag = |key| install.get(key).and_then(json::Value::as_bool);
+            packages.push(InstalledPackage {
+                name,
+                version,
+                source,
+                bins: strings("bins")?,
+                version_req: string("version_req").map(str::to_string),
+                features: strings("features")?,
+                all_features: flag("all_features").unwrap_or_default(),
+                no_default_features: flag("no_default_features").unwrap_or_default(),
+                profile: string("profile").map(str::to_string),
+                target: string("target").map(str::to_string),
+                rustc: string("rustc").map(str::to_string),
+            });
+        }
+        packages.sort_by(|a, b| (&a.name, &a.version).cmp(&(&b.name, &b.version)));
+        Ok(packages)
+    }
+
+    /// Parse the contents of a `.crates.toml` file
+    pub fn parse_crates_toml(s: &str) -> io::Result<Vec<InstalledPackage>> {
+        let mut packages = Vec::new();
+        for line in s.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') || line.starts_with('[') {
+                continue;
+            }
+            let end = string_end(line).ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("expected a quoted package id, got `{}`", line),
+                )
+            })?;
+            let id = parse_string(&line[..end])?;
+            let bins = line[end..].trim_start().strip_prefix('=').ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("expected `\"<id>\" = [...]`, got `{}`", line),
+                )
+            })?;
+            let (name, version, source) = parse_package_id(&id)?;
+            packages.push(InstalledPackage {
+                name,
+                version,
+                source,
+                bins: parse_string_array(bins)?,
+                ..Default::default()
+            });
+        }
+        packages.sort_by(|a, b| (&a.name, &a.version).cmp(&(&b.name, &b.version)));
+        Ok(packages)
+    }
+}
+
+/// Split a `<name> <version> (<source>)` package id
+fn parse_package_id(id: &str) -> io::Result<(String, String, String)> {
+    id.split_once(' ')
+        .and_then(|(name, rest)| {
+            let (version, source) = rest.split_once(" (")?;
+            let source = source.strip_suffix(')')?;
+            Some((name.to_string(), version.to_string(), source.to_string()))
+        })
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("invalid package id `{}`", id),
+            )
+        })
+}
+
 #[cfg(test)]
 mod test {
-    use super::Install;
+    use super::{Install, InstalledPackage};
     use clap::CommandFactory;
 
     #[test]
     fn verify_cli() {
         <Install as CommandFactory>::command().debug_assert()
     }
+
+    #[test]
+    fn parse_crates_toml() {
+        let packages = InstalledPackage::parse_crates_toml(
+            r#"[v1]
+"ripgrep 14.1.0 (registry+https://github.com/rust-lang/crates.io-index)" = ["rg"]
+"cargo-options 0.7.6 (path+file:///tmp/cargo-options)" = []
+"#,
+        )
+        .unwrap();
+        assert_eq!(packages.len(), 2);
+        assert_eq!(packages[0].name, "cargo-options");
+        assert_eq!(packages[0].source, "path+file:///tmp/cargo-options");
+        assert!(packages[0].bins.is_empty());
+        assert_eq!(packages[1].version, "14.1.0");
+        assert_eq!(packages[1].bins, ["rg"]);
+    }
+
+    #[test]
+    fn parse_crates2_json() {
+        let packages = InstalledPackage::parse_crates2_json(
+            r#"{"installs":{"vt 0.1.0 (path+file:///tmp/vt)":{"version_req":null,"bins":["vt"],"features":["color"],"all_features":false,"no_default_features":true,"profile":"release","target":"x86_64-unknown-linux-gnu","rustc":"rustc 1.95.0"}}}"#,
+        )
+        .unwrap();
+        assert_eq!(packages.len(), 1);
+        let vt = &packages[0];
+        assert_eq!((vt.name.as_str(), vt.version.as_str()), ("vt", "0.1.0"));
+        assert_eq!(vt.features, ["color"]);
+        assert!(vt.no_default_features);
+        assert_eq!(vt.profile.as_deref(), Some("release"));
+    }
 }
@@ -1,13 +1,41 @@
+mod add;
 mod build;
+mod cargo;
 mod check;
+mod clean;
 mod clippy;
 mod common;
+mod config;
+mod config_override;
 mod doc;
+mod fetch;
+mod generate_lockfile;
+mod info;
+mod init;
 mod install;
+mod json;
+mod locate_project;
+mod login;
+mod logout;
 mod metadata;
+mod miri;
+mod new;
+mod nextest;
+mod owner;
+mod pkgid;
+mod profile;
+mod remove;
+mod report;
 mod run;
 mod rustc;
+mod rustdoc;
+mod rustflags;
+mod search;
 mod test;
+mod toml;
+mod uninstall;
+mod vendor;
+mod yank;
 
 pub mod heading {
     pub const PACKAGE_SELECTION: &str = "Package Selection";
@@ -15,6 +43,9 @@ pub mod heading {
     pub const FEATURE_SELECTION: &str = "Feature Selection";
     pub const COMPILATION_OPTIONS: &str = "Compilation Options";
     pub const MANIFEST_OPTIONS: &str = "Manifest Options";
+    pub const SOURCE: &str = "Source";
+    pub const SECTION: &str = "Section";
+    pub const PROFILE_OVERRIDES: &str = "Profile Overrides";
 }
 
 pub fn styles() -> clap::builder::Styles {
@@ -32,12 +63,45 @@ pub fn styles() -> clap::builder::Styles {
 
 // Specify crate to satisfy naming overlap w/ rustc clippy
 pub use crate::clippy::Clippy;
+pub use add::{Add, DependencySpec};
 pub use build::Build;
+pub use cargo::{Cargo, CargoVersion, InstalledCommand, InstalledCommands};
 pub use check::Check;
-pub use common::CommonOptions;
+pub use clean::{Clean, CleanSummary};
+pub use common::{
+    CommonOptions, CompilationOptions, FeatureSelection, GlobalOptions, LibBinExampleSelection,
+    ManifestOptions, OptionSource, PackageSelection, Provenance, TargetSelection,
+};
+pub use config::{
+    Config, ConfigEntry, ConfigFormat, ConfigListItem, ConfigOrigin, ConfigValue, ConfigValues,
+};
+pub use config_override::{ConfigOverride, ConfigOverrideValue};
 pub use doc::Doc;
-pub use install::Install;
+pub use fetch::Fetch;
+pub use generate_lockfile::GenerateLockfile;
+pub use info::{Info, PackageFeature, PackageInfo};
+pub use init::Init;
+pub use install::{Install, InstalledPackage};
+pub use locate_project::{LocateProject, LocateProjectFormat};
+pub use login::Login;
+pub use logout::Logout;
 pub use metadata::Metadata;
+pub use miri::{Miri, MiriFlags, MiriMode};
+pub use new::{Edition, New, NewOptions, VersionControl};
+pub use nextest::Nextest;
+pub use owner::Owner;
+pub use pkgid::{PackageIdSpec, Pkgid};
+pub use profile::{
+    DebugInfo, Lto, OptLevel, PanicStrategy, ProfileOverrides, SplitDebuginfo, Strip,
+};
+pub use remove::Remove;
+pub use report::{FutureIncompatItem, FutureIncompatPackage, FutureIncompatReport, Report};
 pub use run::Run;
 pub use rustc::Rustc;
-pub use test::Test;
+pub use rustdoc::{OutputFormat, Rustdoc};
+pub use rustflags::RustFlags;
+pub use search::{Search, SearchResult, SearchResults};
+pub use test::{Test, TestCoverage};
+pub use uninstall::Uninstall;
+pub use vendor::{Vendor, VendorConfig, VendorSource};
+pub use yank::Yank;
@@ -0,0 +1,129 @@
+use std::fmt;
+use std::io;
+use std::ops::{Deref, DerefMut};
+use std::path::PathBuf;
+use std::process::Command;
+
+use clap::{Parser, ValueEnum};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::common::{self, CommonOptions, GlobalOptions};
+use crate::heading;
+
+/// Output representation of `cargo locate-project`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, ValueEnum)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum LocateProjectFormat {
+    Json,
+    Plain,
+}
+
+impl LocateProjectFormat {
+    /// Value passed to `--message-format`
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LocateProjectFormat::Json => "json",
+            LocateProjectFormat::Plain => "plain",
+        }
+    }
+}
+
+impl fmt::Display for LocateProjectFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Print a JSON representation of a Cargo.toml file's location
+#[derive(Clone, Debug, Default, Parser)]
+#[command(
+    display_order = 1,
+    after_help = "Run `cargo help locate-project` for more detailed information."
+)]
+#[group(skip)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct LocateProject {
+    #[command(flatten)]
+    #[cfg_attr(feature = "serde", serde(flatten))]
+    pub global_options: GlobalOptions,
+
+    /// Locate Cargo.toml of the workspace root
+    #[arg(long)]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub workspace: bool,
+
+    /// Output representation
+    #[arg(long, value_name = "FMT")]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub message_format: Option<LocateProjectFormat>,
+
+    /// Path to Cargo.toml
+    #[arg(long, value_name = "PATH", help_heading = heading::MANIFEST_OPTIONS)]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub manifest_path: Option<PathBuf>,
+}
+
+impl LocateProject {
+    /// Build a `cargo locate-project` command
+    pub fn command(&self) -> Command {
+        let mut cmd = CommonOptions::cargo_command();
+        self.global_options.apply_top_level(&mut cmd);
+        cmd.arg("locate-project");
+        self.global_options.apply(&mut cmd);
+        if self.workspace {
+            cmd.arg("--workspace");
+        }
+        if let Some(format) = self.message_format {
+            cmd.arg("--message-format").arg(format.as_str());
+        }
+        if let Some(path) = self.manifest_path.as_ref() {
+            cmd.arg("--manifest-path").arg(path);
+        }
+        cmd
+    }
+
+    /// Run `cargo locate-project` and return the path of the located `Cargo.toml`
+    ///
+    /// `message_format` is always `plain` here, regardless of what was set.
+    pub fn locate(&self) -> io::Result<PathBuf> {
+        let mut locate = self.clone();
+        locate.message_format = Some(LocateProjectFormat::Plain);
+        let stdout = common::capture_stdout(&mut locate.command())?;
+        let path = stdout.trim_end_matches(['\r', '\n']);
+        if path.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "cargo locate-project printed no path",
+            ));
+        }
+        Ok(PathBuf::from(path))
+    }
+}
+
+impl Deref for LocateProject {
+    type Target = GlobalOptions;
+
+    fn deref(&self) -> &Self::Target {
+        &self.global_options
+    }
+}
+
+impl DerefMut for LocateProject {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.global_options
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::LocateProject;
+    use clap::CommandFactory;
+
+    #[test]
+    fn verify_cli() {
+        <LocateProject as CommandFactory>::command().debug_assert()
+    }
+}
@@ -0,0 +1,90 @@
+use std::io;
+use std::ops::{Deref, DerefMut};
+use std::process::Command;
+
+use clap::Parser;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::common::{self, CommonOptions, GlobalOptions};
+
+/// Log in to a registry.
+#[derive(Clone, Debug, Default, Parser)]
+#[command(
+    display_order = 1,
+    after_help = "Run `cargo help login` for more detailed information."
+)]
+#[group(skip)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct Login {
+    #[command(flatten)]
+    #[cfg_attr(feature = "serde", serde(flatten))]
+    pub global_options: GlobalOptions,
+
+    /// Registry to use
+    #[arg(long, value_name = "REGISTRY")]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub registry: Option<String>,
+
+    /// API token to save, written to cargo's stdin by `run`
+    #[arg(skip)]
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub token: Option<String>,
+
+    /// Additional arguments for the credential provider
+    #[arg(value_name = "args", last = true, num_args = 0..)]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub args: Vec<String>,
+}
+
+impl Login {
+    /// Build a `cargo login` command
+    ///
+    /// `token` is left out, the command is safe to log.
+    pub fn command(&self) -> Command {
+        let mut cmd = CommonOptions::cargo_command();
+        self.global_options.apply_top_level(&mut cmd);
+        cmd.arg("login");
+        self.global_options.apply(&mut cmd);
+        if let Some(registry) = self.registry.as_ref() {
+            cmd.arg("--registry").arg(registry);
+        }
+        if !self.args.is_empty() {
+            cmd.arg("--").args(&self.args);
+        }
+        cmd
+    }
+
+    /// Run `cargo login`, writing `token` to its stdin
+    ///
+    /// Without a token cargo prompts on the inherited stdin.
+    pub fn run(&self) -> io::Result<()> {
+        common::run_with_secret(&mut self.command(), self.token.as_deref())
+    }
+}
+
+impl Deref for Login {
+    type Target = GlobalOptions;
+
+    fn deref(&self) -> &Self::Target {
+        &self.global_options
+    }
+}
+
+impl DerefMut for Login {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.global_options
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Login;
+    use clap::CommandFactory;
+
+    #[test]
+    fn verify_cli() {
+        <Login as CommandFactory>::command().debug_assert()
+    }
+}
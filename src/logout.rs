@@ -0,0 +1,67 @@
+use std::ops::{Deref, DerefMut};
+use std::process::Command;
+
+use clap::Parser;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::common::{CommonOptions, GlobalOptions};
+
+/// Remove an API token from the registry locally
+#[derive(Clone, Debug, Default, Parser)]
+#[command(
+    display_order = 1,
+    after_help = "Run `cargo help logout` for more detailed information."
+)]
+#[group(skip)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct Logout {
+    #[command(flatten)]
+    #[cfg_attr(feature = "serde", serde(flatten))]
+    pub global_options: GlobalOptions,
+
+    /// Registry to use
+    #[arg(long, value_name = "REGISTRY")]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub registry: Option<String>,
+}
+
+impl Logout {
+    /// Build a `cargo logout` command
+    pub fn command(&self) -> Command {
+        let mut cmd = CommonOptions::cargo_command();
+        self.global_options.apply_top_level(&mut cmd);
+        cmd.arg("logout");
+        self.global_options.apply(&mut cmd);
+        if let Some(registry) = self.registry.as_ref() {
+            cmd.arg("--registry").arg(registry);
+        }
+        cmd
+    }
+}
+
+impl Deref for Logout {
+    type Target = GlobalOptions;
+
+    fn deref(&self) -> &Self::Target {
+        &self.global_options
+    }
+}
+
+impl DerefMut for Logout {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.global_options
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Logout;
+    use clap::CommandFactory;
+
+    #[test]
+    fn verify_cli() {
+        <Logout as CommandFactory>::command().debug_assert()
+    }
+}
@@ -0,0 +1,228 @@
+use std::env;
+use std::process::Command;
+
+use clap::{ArgAction, Parser, Subcommand};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{CommonOptions, Run, Test};
+
+/// Commonly used `MIRIFLAGS`
+#[derive(Clone, Debug, Default, Parser)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct MiriFlags {
+    /// Give the interpreted program access to the host, e.g. the file system (-Zmiri-disable-isolation)
+    #[arg(long)]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub disable_isolation: bool,
+
+    /// Reject integer-to-pointer casts (-Zmiri-strict-provenance)
+    #[arg(long)]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub strict_provenance: bool,
+
+    /// Run the program with many seeds, optionally a range like 0..64 (-Zmiri-many-seeds)
+    #[arg(long, value_name = "RANGE", num_args = 0..=1, require_equals = true)]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub many_seeds: Option<Option<String>>,
+
+    /// Use the Tree Borrows aliasing model instead of Stacked Borrows (-Zmiri-tree-borrows)
+    #[arg(long)]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub tree_borrows: bool,
+
+    /// Additional flag to add to `MIRIFLAGS`
+    #[arg(long, value_name = "FLAG", action = ArgAction::Append, allow_hyphen_values = true)]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub miri_flag: Vec<String>,
+}
+
+impl MiriFlags {
+    /// The flags in the order they are added to `MIRIFLAGS`
+    pub fn flags(&self) -> Vec<String> {
+        let mut flags = Vec::new();
+        if self.disable_isolation {
+            flags.push("-Zmiri-disable-isolation".to_string());
+        }
+        if self.strict_provenance {
+            flags.push("-Zmiri-strict-provenance".to_string());
+        }
+        match &self.many_seeds {
+            Some(Some(range)) => flags.push(format!("-Zmiri-many-seeds={}", range)),
+            Some(None) => flags.push("-Zmiri-many-seeds".to_string()),
+            None => {}
+        }
+        if self.tree_borrows {
+            flags.push("-Zmiri-tree-borrows".to_string());
+        }
+        flags.extend(self.miri_flag.iter().cloned());
+        flags
+    }
+
+    /// Append the flags to the `MIRIFLAGS` inherited from the environment
+    pub fn apply(&self, cmd: &mut Command) {
+        let flags = self.flags();
+        if flags.is_empty() {
+            return;
+        }
+        let mut miriflags = env::var("MIRIFLAGS").unwrap_or_default();
+        for flag in flags {
+            if !miriflags.is_empty() {
+                miriflags.push(' ');
+            }
+            miriflags.push_str(&flag);
+        }
+        cmd.env("MIRIFLAGS", miriflags);
+    }
+}
+
+/// What to run under Miri
+#[derive(Clone, Debug, Default, Subcommand)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum MiriMode {
+    /// Run tests under Miri
+    Test(Test),
+    /// Run a binary under Miri
+    Run(Run),
+    /// Only set up the Miri sysroot
+    #[default]
+    Setup,
+}
+
+/// Run a crate's tests or binaries under the Miri interpreter
+#[derive(Clone, Debug, Default, Parser)]
+#[command(
+    display_order = 1,
+    after_help = "Run `cargo miri --help` for more detailed information."
+)]
+#[group(skip)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct Miri {
+    /// Toolchain to run, e.g. `nightly`, via `cargo +<TOOLCHAIN>` (Miri is nightly-only)
+    #[arg(long, value_name = "TOOLCHAIN")]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub toolchain: Option<String>,
+
+    #[command(flatten)]
+    #[cfg_attr(feature = "serde", serde(flatten))]
+    pub flags: MiriFlags,
+
+    #[command(subcommand)]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub mode: MiriMode,
+}
+
+impl Miri {
+    /// Build a `cargo miri` command
+    pub fn command(&self) -> Command {
+        let mut cmd = CommonOptions::toolchain_cargo_command(self.toolchain.as_deref());
+        // `-C` has to come before `miri` rather than before the inner subcommand
+        match &self.mode {
+            MiriMode::Test(test) => {
+                let mut test = test.clone();
+                test.apply_top_level(&mut cmd);
+                test.directory = None;
+                cmd.arg("miri");
+                nest(&mut cmd, &test.command());
+            }
+            MiriMode::Run(run) => {
+                let mut run = run.clone();
+                run.apply_top_level(&mut cmd);
+                run.directory = None;
+                cmd.arg("miri");
+                nest(&mut cmd, &run.command());
+            }
+            MiriMode::Setup => {
+                cmd.arg("miri").arg("setup");
+            }
+        }
+        self.flags.apply(&mut cmd);
+        cmd
+    }
+}
+
+/// Append `inner`'s arguments to `cmd`, along with the environment and
+/// working directory it was set up with
+fn nest(cmd: &mut Command, inner: &Command) {
+    cmd.args(inner.get_args());
+    for (key, value) in inner.get_envs() {
+        match value {
+            Some(value) => cmd.env(key, value),
+            None => cmd.env_remove(key),
+        };
+    }
+    if let Some(dir) = inner.get_current_dir() {
+        cmd.current_dir(dir);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{nest, Miri};
+    use clap::{CommandFactory, Parser};
+    use std::path::Path;
+    use std::process::Command;
+
+    #[test]
+    fn verify_cli() {
+        <Miri as CommandFactory>::command().debug_assert()
+    }
+
+    #[test]
+    fn miri_test() {
+        let miri = Miri::parse_from([
+            "miri",
+            "--toolchain",
+            "nightly",
+            "--strict-provenance",
+            "--many-seeds=0..8",
+            "--miri-flag",
+            "-Zmiri-backtrace=full",
+            "test",
+            "-p",
+            "foo",
+        ]);
+        let cmd = miri.command();
+        assert_eq!(cmd.get_program(), "cargo");
+        let args: Vec<_> = cmd.get_args().map(|a| a.to_owned()).collect();
+        assert_eq!(args, ["+nightly", "miri", "test", "--package", "foo", "--"]);
+        let miriflags = cmd
+            .get_envs()
+            .find(|(key, _)| *key == "MIRIFLAGS")
+            .and_then(|(_, value)| value)
+            .unwrap()
+            .to_string_lossy()
+            .into_owned();
+        assert!(miriflags
+            .ends_with("-Zmiri-strict-provenance -Zmiri-many-seeds=0..8 -Zmiri-backtrace=full"));
+    }
+
+    #[test]
+    fn nested_command_env() {
+        let mut inner = Command::new("cargo");
+        inner
+            .arg("test")
+            .env("CARGO_ENCODED_RUSTFLAGS", "--cfg\x1fmiri_test")
+            .env_remove("RUSTFLAGS")
+            .current_dir("crates/foo");
+        let mut cmd = Command::new("cargo");
+        cmd.arg("miri");
+        nest(&mut cmd, &inner);
+        let args: Vec<_> = cmd.get_args().collect();
+        assert_eq!(args, ["miri", "test"]);
+        let envs: Vec<_> = cmd.get_envs().collect();
+        assert_eq!(
+            envs,
+            [
+                (
+                    "CARGO_ENCODED_RUSTFLAGS".as_ref(),
+                    Some("--cfg\x1fmiri_test".as_ref())
+                ),
+                ("RUSTFLAGS".as_ref(), None)
+            ]
+        );
+        assert_eq!(cmd.get_current_dir(), Some(Path::new("crates/foo")));
+    }
+}
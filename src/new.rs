@@ -0,0 +1,225 @@
+use std::fmt;
+use std::ops::{Deref, DerefMut};
+use std::path::PathBuf;
+use std::process::Command;
+
+use clap::{Parser, ValueEnum};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::common::{CommonOptions, GlobalOptions};
+
+/// Rust edition of a generated package
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, ValueEnum)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub enum Edition {
+    #[value(name = "2015")]
+    #[cfg_attr(feature = "serde", serde(rename = "2015"))]
+    Edition2015,
+    #[value(name = "2018")]
+    #[cfg_attr(feature = "serde", serde(rename = "2018"))]
+    Edition2018,
+    #[value(name = "2021")]
+    #[cfg_attr(feature = "serde", serde(rename = "2021"))]
+    Edition2021,
+    #[value(name = "2024")]
+    #[cfg_attr(feature = "serde", serde(rename = "2024"))]
+    Edition2024,
+}
+
+impl Edition {
+    /// Value passed to `--edition`
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Edition::Edition2015 => "2015",
+            Edition::Edition2018 => "2018",
+            Edition::Edition2021 => "2021",
+            Edition::Edition2024 => "2024",
+        }
+    }
+}
+
+impl fmt::Display for Edition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Version control system to initialize a generated package with
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, ValueEnum)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum VersionControl {
+    Git,
+    Hg,
+    Pijul,
+    Fossil,
+    None,
+}
+
+impl VersionControl {
+    /// Value passed to `--vcs`
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            VersionControl::Git => "git",
+            VersionControl::Hg => "hg",
+            VersionControl::Pijul => "pijul",
+            VersionControl::Fossil => "fossil",
+            VersionControl::None => "none",
+        }
+    }
+}
+
+impl fmt::Display for VersionControl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// `cargo new` options which are also accepted by `cargo init`
+#[derive(Clone, Debug, Default, Parser)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct NewOptions {
+    #[command(flatten)]
+    #[cfg_attr(feature = "serde", serde(flatten))]
+    pub global_options: GlobalOptions,
+
+    /// Initialize a new repository for the given version control system,
+    /// overriding a global configuration.
+    #[arg(long, value_name = "VCS")]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub vcs: Option<VersionControl>,
+
+    /// Use a binary (application) template [default]
+    #[arg(long, conflicts_with = "lib")]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub bin: bool,
+
+    /// Use a library template
+    #[arg(long)]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub lib: bool,
+
+    /// Edition to set for the crate generated
+    #[arg(long, value_name = "YEAR")]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub edition: Option<Edition>,
+
+    /// Set the resulting package name, defaults to the directory name
+    #[arg(long, value_name = "NAME")]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub name: Option<String>,
+
+    /// Registry to use
+    #[arg(long, value_name = "REGISTRY")]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub registry: Option<String>,
+}
+
+impl NewOptions {
+    /// Apply options to `Command`
+    pub fn apply(&self, cmd: &mut Command) {
+        self.global_options.apply(cmd);
+        if let Some(vcs) = self.vcs {
+            cmd.arg("--vcs").arg(vcs.as_str());
+        }
+        if self.bin {
+            cmd.arg("--bin");
+        }
+        if self.lib {
+            cmd.arg("--lib");
+        }
+        if let Some(edition) = self.edition {
+            cmd.arg("--edition").arg(edition.as_str());
+        }
+        if let Some(name) = self.name.as_ref() {
+            cmd.arg("--name").arg(name);
+        }
+        if let Some(registry) = self.registry.as_ref() {
+            cmd.arg("--registry").arg(registry);
+        }
+    }
+}
+
+impl Deref for NewOptions {
+    type Target = GlobalOptions;
+
+    fn deref(&self) -> &Self::Target {
+        &self.global_options
+    }
+}
+
+impl DerefMut for NewOptions {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.global_options
+    }
+}
+
+/// Create a new cargo package at <path>
+#[derive(Clone, Debug, Default, Parser)]
+#[command(
+    display_order = 1,
+    after_help = "Run `cargo help new` for more detailed information."
+)]
+#[group(skip)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct New {
+    #[command(flatten)]
+    #[cfg_attr(feature = "serde", serde(flatten))]
+    pub new: NewOptions,
+
+    #[arg(value_name = "PATH")]
+    pub path: PathBuf,
+}
+
+impl New {
+    /// Build a `cargo new` command
+    pub fn command(&self) -> Command {
+        let mut cmd = CommonOptions::cargo_command();
+        self.new.global_options.apply_top_level(&mut cmd);
+        cmd.arg("new");
+
+        self.new.apply(&mut cmd);
+        cmd.arg(&self.path);
+
+        cmd
+    }
+}
+
+impl Deref for New {
+    type Target = NewOptions;
+
+    fn deref(&self) -> &Self::Target {
+        &self.new
+    }
+}
+
+impl DerefMut for New {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.new
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Edition, New, VersionControl};
+    use clap::{CommandFactory, Parser};
+
+    #[test]
+    fn verify_cli() {
+        <New as CommandFactory>::command().debug_assert()
+    }
+
+    #[test]
+    fn typed_values() {
+        let new = New::parse_from(["new", "--lib", "--edition", "2021", "--vcs", "none", "foo"]);
+        assert_eq!(new.edition, Some(Edition::Edition2021));
+        assert_eq!(new.vcs, Some(VersionControl::None));
+        let args: Vec<_> = new.command().get_args().map(|a| a.to_owned()).collect();
+        assert_eq!(
+            args,
+            ["new", "--vcs", "none", "--lib", "--edition", "2021", "foo"]
+        );
+    }
+}
@@ -0,0 +1,381 @@
+use std::io;
+use std::ops::{Deref, DerefMut};
+use std::path::PathBuf;
+use std::process::Command;
+
+use clap::{ArgAction, Parser};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::common::{
+    CommonOptions, CompilationOptions, ManifestOptions, PackageSelection, TargetSelection,
+};
+use crate::{CargoVersion, Test};
+
+/// Build and run tests with cargo-nextest
+#[derive(Clone, Debug, Default, Parser)]
+#[command(
+    display_order = 1,
+    after_help = "Run `cargo nextest run --help` for more detailed information.",
+    // `--profile` selects the nextest profile, like for `cargo nextest run`
+    mut_arg("profile", |arg| arg.long("cargo-profile")),
+)]
+#[group(skip)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct Nextest {
+    #[command(flatten)]
+    #[cfg_attr(feature = "serde", serde(flatten))]
+    pub common: CommonOptions,
+
+    #[command(flatten)]
+    #[cfg_attr(feature = "serde", serde(flatten))]
+    pub compilation_options: CompilationOptions,
+
+    #[command(flatten)]
+    #[cfg_attr(feature = "serde", serde(flatten))]
+    pub manifest_options: ManifestOptions,
+
+    #[command(flatten)]
+    #[cfg_attr(feature = "serde", serde(flatten))]
+    pub package_selection: PackageSelection,
+
+    #[command(flatten)]
+    #[cfg_attr(feature = "serde", serde(flatten))]
+    pub target_selection: TargetSelection,
+
+    /// Outputs a future incompatibility report at the end of the build (unstable)
+    #[arg(long)]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub future_incompat_report: bool,
+
+    /// Nextest profile to use
+    // The cargo profile is `common.profile`, renamed to `--cargo-profile`
+    #[arg(short = 'P', long = "profile", value_name = "PROFILE")]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub nextest_profile: Option<String>,
+
+    /// Compile, but don't run tests
+    #[arg(long)]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub no_run: bool,
+
+    /// Run all tests regardless of failure
+    #[arg(long)]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub no_fail_fast: bool,
+
+    /// Number of tests to run simultaneously [possible values: integer or "num-cpus"]
+    #[arg(long, value_name = "THREADS")]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub test_threads: Option<String>,
+
+    /// Number of retries for failing tests
+    #[arg(long, value_name = "RETRIES")]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub retries: Option<u32>,
+
+    /// Run tests serially and do not capture output
+    #[arg(long, alias = "nocapture")]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub no_capture: bool,
+
+    /// Test partition, e.g. hash:1/2 or count:2/3
+    #[arg(long, value_name = "PARTITION")]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub partition: Option<String>,
+
+    /// Test filterset (see `cargo nextest help filtersets`)
+    #[arg(
+        short = 'E',
+        long = "filterset",
+        alias = "filter-expr",
+        value_name = "EXPR",
+        action = ArgAction::Append,
+    )]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub filter_expr: Vec<String>,
+
+    /// Path to nextest archive to run tests from, instead of building
+    #[arg(long, value_name = "PATH")]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub archive_file: Option<PathBuf>,
+
+    /// Test name filters
+    #[arg(value_name = "FILTERS")]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub filters: Vec<String>,
+
+    /// Arguments for the test binary
+    #[arg(value_name = "args", last = true, num_args = 0..)]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub args: Vec<String>,
+}
+
+impl Nextest {
+    /// Build a `cargo nextest run` command
+    ///
+    /// Cargo options in `common` are translated to nextest's spelling, e.g.
+    /// `--jobs` becomes `--build-jobs`. `--keep-going`, `--message-format`,
+    /// `--lockfile-path` and more than one `--target` have no nextest
+    /// equivalent and are an error.
+    pub fn command(&self) -> io::Result<Command> {
+        let mut cmd = CommonOptions::cargo_command();
+        self.common.global_options.apply_top_level(&mut cmd);
+        cmd.arg("nextest").arg("run");
+
+        self.apply_common(&mut cmd)?;
+
+        self.compilation_options.apply(&mut cmd);
+        self.manifest_options.apply(&mut cmd);
+        self.package_selection.apply(&mut cmd);
+        self.target_selection.apply(&mut cmd);
+        if self.future_incompat_report {
+            cmd.arg("--future-incompat-report");
+        }
+        if let Some(profile) = self.nextest_profile.as_ref() {
+            cmd.arg("--profile").arg(profile);
+        }
+        if self.no_run {
+            cmd.arg("--no-run");
+        }
+        if self.no_fail_fast {
+            cmd.arg("--no-fail-fast");
+        }
+        if let Some(threads) = self.test_threads.as_ref() {
+            cmd.arg("--test-threads").arg(threads);
+        }
+        if let Some(retries) = self.retries {
+            cmd.arg("--retries").arg(retries.to_string());
+        }
+        if self.no_capture {
+            cmd.arg("--no-capture");
+        }
+        if let Some(partition) = self.partition.as_ref() {
+            cmd.arg("--partition").arg(partition);
+        }
+        for expr in &self.filter_expr {
+            cmd.arg("-E").arg(expr);
+        }
+        if let Some(archive) = self.archive_file.as_ref() {
+            cmd.arg("--archive-file").arg(archive);
+        }
+        cmd.args(&self.filters);
+        if !self.args.is_empty() {
+            cmd.arg("--").args(&self.args);
+        }
+
+        Ok(cmd)
+    }
+
+    /// Build a `cargo nextest run` command for the cargo at `version`
+    ///
+    /// `-Z unstable-options` is added when an option is unstable on `version`
+    /// and options `version` doesn't have (anymore) are an error.
+    pub fn command_for(&self, version: &CargoVersion) -> io::Result<Command> {
+        let mut nextest = self.clone();
+        nextest.common.adapt_to_version(version)?;
+        nextest.command()
+    }
+
+    /// Unstable options in use, cargo only takes them with `-Z unstable-options`
+    pub fn unstable_options(&self) -> Vec<&'static str> {
+        let mut options = self.common.unstable_options();
+        options.extend(self.compilation_options.unstable_options());
+        options
+    }
+
+    /// Whether the options only work on a nightly cargo
+    pub fn requires_nightly(&self) -> bool {
+        self.common.requires_nightly_for(&self.unstable_options())
+    }
+
+    /// `CommonOptions::apply` with nextest's names for cargo options
+    fn apply_common(&self, cmd: &mut Command) -> io::Result<()> {
+        let common = &self.common;
+        let unsupported = [
+            (common.keep_going, "--keep-going"),
+            (!common.message_format.is_empty(), "--message-format"),
+            (common.lockfile_path.is_some(), "--lockfile-path"),
+            (common.target.len() > 1, "more than one `--target`"),
+        ];
+        if let Some((_, option)) = unsupported.iter().find(|(set, _)| *set) {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!("{} is not supported by cargo-nextest", option),
+            ));
+        }
+
+        let (quiet, verbose) = common.quiet_and_verbose();
+        if quiet {
+            cmd.arg("--cargo-quiet");
+        }
+        for _ in 0..verbose {
+            cmd.arg("--cargo-verbose");
+        }
+        common
+            .global_options
+            .apply_without_verbosity(cmd, &self.unstable_options());
+        if let Some(jobs) = common.jobs_arg() {
+            cmd.arg("--build-jobs").arg(jobs);
+        }
+        if let Some(profile) = common.profile.as_ref() {
+            cmd.arg("--cargo-profile").arg(profile);
+        }
+        common.feature_selection.apply(cmd);
+        for target in common.target_args() {
+            cmd.arg("--target").arg(target);
+        }
+        if let Some(dir) = common.target_dir_arg() {
+            cmd.arg("--target-dir").arg(dir);
+        }
+        if let Some(timings) = common.timings_arg() {
+            cmd.arg(timings);
+        }
+        Ok(())
+    }
+}
+
+impl From<&Test> for Nextest {
+    /// Run the same selection with nextest, `--doc` has no nextest equivalent
+    fn from(test: &Test) -> Self {
+        Self {
+            // `test.common.profile` is the cargo profile, passed as `--cargo-profile`
+            common: test.common.clone(),
+            compilation_options: test.compilation_options.clone(),
+            manifest_options: test.manifest_options.clone(),
+            package_selection: test.package_selection.clone(),
+            target_selection: test.target_selection.clone(),
+            future_incompat_report: test.future_incompat_report,
+            no_run: test.no_run,
+            no_fail_fast: test.no_fail_fast,
+            filters: test.test_name.iter().cloned().collect(),
+            args: test.args.clone(),
+            ..Default::default()
+        }
+    }
+}
+
+impl Deref for Nextest {
+    type Target = CommonOptions;
+
+    fn deref(&self) -> &Self::Target {
+        &self.common
+    }
+}
+
+impl DerefMut for Nextest {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.common
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Nextest;
+    use crate::Test;
+    use clap::{CommandFactory, Parser};
+    use std::io;
+
+    #[test]
+    fn verify_cli() {
+        <Nextest as CommandFactory>::command().debug_assert()
+    }
+
+    #[test]
+    fn from_test() {
+        let test = Test::parse_from([
+            "test",
+            "--profile",
+            "ci",
+            "-j",
+            "4",
+            "-p",
+            "foo",
+            "--lib",
+            "some_test",
+        ]);
+        let mut nextest = Nextest::from(&test);
+        nextest.retries = Some(2);
+        nextest.filter_expr.push("not test(slow)".to_string());
+        let args: Vec<_> = nextest
+            .command()
+            .unwrap()
+            .get_args()
+            .map(|a| a.to_owned())
+            .collect();
+        assert_eq!(
+            args,
+            [
+                "nextest",
+                "run",
+                "--build-jobs",
+                "4",
+                "--cargo-profile",
+                "ci",
+                "--package",
+                "foo",
+                "--lib",
+                "--retries",
+                "2",
+                "-E",
+                "not test(slow)",
+                "some_test"
+            ]
+        );
+    }
+
+    #[test]
+    fn profiles() {
+        let nextest = Nextest::parse_from([
+            "nextest",
+            "-P",
+            "ci",
+            "--cargo-profile",
+            "release-lto",
+            "-v",
+        ]);
+        assert_eq!(nextest.nextest_profile.as_deref(), Some("ci"));
+        assert_eq!(nextest.common.profile.as_deref(), Some("release-lto"));
+        let args: Vec<_> = nextest
+            .command()
+            .unwrap()
+            .get_args()
+            .map(|a| a.to_owned())
+            .collect();
+        assert_eq!(
+            args,
+            [
+                "nextest",
+                "run",
+                "--cargo-verbose",
+                "--cargo-profile",
+                "release-lto",
+                "--profile",
+                "ci"
+            ]
+        );
+        let nextest = Nextest::parse_from(["nextest", "--profile", "ci"]);
+        assert_eq!(nextest.nextest_profile.as_deref(), Some("ci"));
+        assert_eq!(nextest.common.profile, None);
+    }
+
+    #[test]
+    fn unsupported_options() {
+        for args in [
+            &["nextest", "--keep-going"][..],
+            &["nextest", "--message-format", "json"],
+            &["nextest", "--lockfile-path", "Cargo.lock"],
+            &[
+                "nextest",
+                "--target",
+                "wasm32-wasip1",
+                "--target",
+                "x86_64-unknown-linux-gnu",
+            ],
+        ] {
+            let err = Nextest::parse_from(args).command().unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::Unsupported);
+        }
+    }
+}
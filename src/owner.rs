@@ -0,0 +1,178 @@
+use std::io;
+use std::ops::{Deref, DerefMut};
+use std::process::Command;
+
+use clap::{ArgAction, Parser};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::common::{self, CommonOptions, GlobalOptions};
+
+/// Manage the owners of a crate on the registry
+#[derive(Clone, Debug, Default, Parser)]
+#[command(
+    display_order = 1,
+    after_help = "Run `cargo help owner` for more detailed information."
+)]
+#[group(skip)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct Owner {
+    #[command(flatten)]
+    #[cfg_attr(feature = "serde", serde(flatten))]
+    pub global_options: GlobalOptions,
+
+    /// Name of a user or team to invite as an owner
+    #[arg(
+        short = 'a',
+        long,
+        value_name = "LOGIN",
+        value_delimiter = ',',
+        action = ArgAction::Append,
+    )]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub add: Vec<String>,
+
+    /// Name of a user or team to remove as an owner
+    #[arg(
+        short = 'r',
+        long,
+        value_name = "LOGIN",
+        value_delimiter = ',',
+        action = ArgAction::Append,
+    )]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub remove: Vec<String>,
+
+    /// List owners of a crate
+    #[arg(short = 'l', long)]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub list: bool,
+
+    /// Registry index URL to modify owners for
+    #[arg(long, value_name = "INDEX", conflicts_with = "registry")]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub index: Option<String>,
+
+    /// Registry to modify owners for
+    #[arg(long, value_name = "REGISTRY")]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub registry: Option<String>,
+
+    /// API token to use when authenticating
+    ///
+    /// Never passed on the command line, see `run`.
+    #[arg(skip)]
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub token: Option<String>,
+
+    #[arg(value_name = "CRATE")]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub crate_name: Option<String>,
+}
+
+impl Owner {
+    /// Build a `cargo owner` command
+    ///
+    /// `token` is left out, the command is safe to log.
+    pub fn command(&self) -> Command {
+        let mut cmd = CommonOptions::cargo_command();
+        self.global_options.apply_top_level(&mut cmd);
+        cmd.arg("owner");
+        self.global_options.apply(&mut cmd);
+        for login in &self.add {
+            cmd.arg("--add").arg(login);
+        }
+        for login in &self.remove {
+            cmd.arg("--remove").arg(login);
+        }
+        if self.list {
+            cmd.arg("--list");
+        }
+        common::apply_registry(
+            &mut cmd,
+            self.registry.as_deref(),
+            self.index.as_deref(),
+            self.token.is_some(),
+        );
+        if let Some(crate_name) = self.crate_name.as_ref() {
+            cmd.arg(crate_name);
+        }
+        cmd
+    }
+
+    /// Run `cargo owner`, passing `token` through the environment
+    ///
+    /// Unlike the `Debug` output of a `Command` with the token set, errors
+    /// returned here never include it.
+    pub fn run(&self) -> io::Result<()> {
+        common::run_with_secret(&mut self.command_with_token(), None)
+    }
+
+    /// `command` with `token` exported to the registry's token variable
+    fn command_with_token(&self) -> Command {
+        let mut cmd = self.command();
+        if let Some(token) = self.token.as_ref() {
+            cmd.env(
+                common::registry_token_env(self.registry.as_deref(), self.index.as_deref()),
+                token,
+            );
+        }
+        cmd
+    }
+}
+
+impl Deref for Owner {
+    type Target = GlobalOptions;
+
+    fn deref(&self) -> &Self::Target {
+        &self.global_options
+    }
+}
+
+impl DerefMut for Owner {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.global_options
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Owner;
+    use clap::{CommandFactory, Parser};
+
+    #[test]
+    fn verify_cli() {
+        <Owner as CommandFactory>::command().debug_assert()
+    }
+
+    #[test]
+    fn token_stays_out_of_argv() {
+        let mut owner =
+            Owner::parse_from(["owner", "--add", "alice,bob", "--registry", "my-reg", "foo"]);
+        owner.token = Some("s3cr3t".to_string());
+        let cmd = owner.command();
+        assert!(!format!("{:?}", cmd).contains("s3cr3t"));
+        let args: Vec<_> = cmd.get_args().map(|a| a.to_owned()).collect();
+        assert_eq!(
+            args,
+            [
+                "owner",
+                "--add",
+                "alice",
+                "--add",
+                "bob",
+                "--registry",
+                "my-reg",
+                "foo"
+            ]
+        );
+        let cmd = owner.command_with_token();
+        let token = cmd
+            .get_envs()
+            .find(|(key, _)| *key == "CARGO_REGISTRIES_MY_REG_TOKEN")
+            .and_then(|(_, value)| value);
+        assert_eq!(token, Some("s3cr3t".as_ref()));
+        assert_eq!(cmd.get_args().count(), args.len());
+    }
+}
@@ -0,0 +1,254 @@
+use std::fmt;
+use std::io;
+use std::ops::{Deref, DerefMut};
+use std::path::PathBuf;
+use std::process::Command;
+use std::str::FromStr;
+
+use clap::Parser;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::common::{self, CommonOptions, GlobalOptions};
+use crate::heading;
+
+/// Print a fully qualified package specification
+#[derive(Clone, Debug, Default, Parser)]
+#[command(
+    display_order = 1,
+    after_help = "Run `cargo help pkgid` for more detailed information."
+)]
+#[group(skip)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct Pkgid {
+    #[command(flatten)]
+    #[cfg_attr(feature = "serde", serde(flatten))]
+    pub global_options: GlobalOptions,
+
+    /// Argument to get the package ID specifier for
+    #[arg(
+        short = 'p',
+        long,
+        value_name = "SPEC",
+        num_args=0..=1,
+        conflicts_with = "spec",
+        help_heading = heading::PACKAGE_SELECTION,
+    )]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub package: Option<String>,
+
+    /// Path to Cargo.toml
+    #[arg(long, value_name = "PATH", help_heading = heading::MANIFEST_OPTIONS)]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub manifest_path: Option<PathBuf>,
+
+    #[arg(value_name = "SPEC")]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub spec: Option<String>,
+}
+
+impl Pkgid {
+    /// Build a `cargo pkgid` command
+    pub fn command(&self) -> Command {
+        let mut cmd = CommonOptions::cargo_command();
+        self.global_options.apply_top_level(&mut cmd);
+        cmd.arg("pkgid");
+        self.global_options.apply(&mut cmd);
+        if let Some(package) = self.package.as_ref() {
+            cmd.arg("--package").arg(package);
+        }
+        if let Some(path) = self.manifest_path.as_ref() {
+            cmd.arg("--manifest-path").arg(path);
+        }
+        if let Some(spec) = self.spec.as_ref() {
+            cmd.arg(spec);
+        }
+        cmd
+    }
+
+    /// Run `cargo pkgid` and parse the package ID specification it prints
+    pub fn package_id_spec(&self) -> io::Result<PackageIdSpec> {
+        let stdout = common::capture_stdout(&mut self.command())?;
+        stdout.trim().parse()
+    }
+}
+
+impl Deref for Pkgid {
+    type Target = GlobalOptions;
+
+    fn deref(&self) -> &Self::Target {
+        &self.global_options
+    }
+}
+
+impl DerefMut for Pkgid {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.global_options
+    }
+}
+
+/// A package ID specification, see `cargo help pkgid`
+///
+/// For example `registry+https://github.com/rust-lang/crates.io-index#serde@1.0.217`
+/// or `path+file:///path/to/foo#0.1.0`.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct PackageIdSpec {
+    /// Package name
+    pub name: String,
+    /// Package version, if the spec pins one
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub version: Option<String>,
+    /// Source kind, e.g. `registry`, `git` or `path`
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub kind: Option<String>,
+    /// Source URL, without the kind prefix
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub url: Option<String>,
+}
+
+impl PackageIdSpec {
+    /// Last path segment of the URL, which names the package when
+    /// the fragment only carries a version
+    fn url_name(&self) -> Option<&str> {
+        let url = self.url.as_deref()?;
+        let path = url.split(['?', '#']).next().unwrap_or(url);
+        path.trim_end_matches('/').rsplit('/').next()
+    }
+}
+
+impl FromStr for PackageIdSpec {
+    type Err = io::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("invalid package ID specification `{}`", s),
+            )
+        };
+        let split_name_version = |spec: &str| match spec.split_once(['@', ':']) {
+            Some((name, version)) => (name.to_string(), Some(version.to_string())),
+            None => (spec.to_string(), None),
+        };
+
+        if !s.contains("://") {
+            let (name, version) = split_name_version(s);
+            if name.is_empty() || version.as_deref() == Some("") {
+                return Err(invalid());
+            }
+            return Ok(Self {
+                name,
+                version,
+                ..Default::default()
+            });
+        }
+
+        let (url, fragment) = match s.split_once('#') {
+            Some((url, fragment)) => (url, Some(fragment)),
+            None => (s, None),
+        };
+        let (scheme, _) = url.split_once("://").ok_or_else(invalid)?;
+        let (kind, url) = match scheme.split_once('+') {
+            Some((kind, _)) => (Some(kind.to_string()), &url[kind.len() + 1..]),
+            None => (None, url),
+        };
+        let mut spec = Self {
+            kind,
+            url: Some(url.to_string()),
+            ..Default::default()
+        };
+        match fragment {
+            Some(fragment) if fragment.starts_with(|c: char| c.is_ascii_digit()) => {
+                spec.name = spec.url_name().ok_or_else(invalid)?.to_string();
+                spec.version = Some(fragment.to_string());
+            }
+            Some(fragment) => {
+                let (name, version) = split_name_version(fragment);
+                spec.name = name;
+                spec.version = version;
+            }
+            None => spec.name = spec.url_name().ok_or_else(invalid)?.to_string(),
+        }
+        if spec.name.is_empty() {
+            return Err(invalid());
+        }
+        Ok(spec)
+    }
+}
+
+impl fmt::Display for PackageIdSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Some(url) = self.url.as_ref() else {
+            write!(f, "{}", self.name)?;
+            if let Some(version) = self.version.as_ref() {
+                write!(f, "@{}", version)?;
+            }
+            return Ok(());
+        };
+        if let Some(kind) = self.kind.as_ref() {
+            write!(f, "{}+", kind)?;
+        }
+        write!(f, "{}", url)?;
+        let name_in_url = self.url_name() == Some(self.name.as_str());
+        match (name_in_url, self.version.as_ref()) {
+            (true, Some(version)) => write!(f, "#{}", version),
+            (true, None) => Ok(()),
+            (false, Some(version)) => write!(f, "#{}@{}", self.name, version),
+            (false, None) => write!(f, "#{}", self.name),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{PackageIdSpec, Pkgid};
+    use clap::CommandFactory;
+
+    #[test]
+    fn verify_cli() {
+        <Pkgid as CommandFactory>::command().debug_assert()
+    }
+
+    #[test]
+    fn parse_package_id_spec() {
+        let cases = [
+            (
+                "registry+https://github.com/rust-lang/crates.io-index#anstyle@1.0.14",
+                ("anstyle", Some("1.0.14"), Some("registry")),
+                "https://github.com/rust-lang/crates.io-index",
+            ),
+            (
+                "path+file:///tmp/vt#0.1.0",
+                ("vt", Some("0.1.0"), Some("path")),
+                "file:///tmp/vt",
+            ),
+            (
+                "git+https://github.com/messense/cargo-options?branch=main#0.7.6",
+                ("cargo-options", Some("0.7.6"), Some("git")),
+                "https://github.com/messense/cargo-options?branch=main",
+            ),
+            (
+                "https://github.com/rust-lang/crates.io-index#regex",
+                ("regex", None, None),
+                "https://github.com/rust-lang/crates.io-index",
+            ),
+        ];
+        for (input, (name, version, kind), url) in cases {
+            let spec: PackageIdSpec = input.parse().unwrap();
+            assert_eq!(spec.name, name);
+            assert_eq!(spec.version.as_deref(), version);
+            assert_eq!(spec.kind.as_deref(), kind);
+            assert_eq!(spec.url.as_deref(), Some(url));
+            assert_eq!(spec.to_string(), input);
+        }
+
+        let spec: PackageIdSpec = "serde@1.0.217".parse().unwrap();
+        assert_eq!(spec.name, "serde");
+        assert_eq!(spec.version.as_deref(), Some("1.0.217"));
+        assert_eq!(spec.url, None);
+        assert_eq!(spec.to_string(), "serde@1.0.217");
+        assert!("@1.0.0".parse::<PackageIdSpec>().is_err());
+    }
+}
@@ -0,0 +1,446 @@
+use std::fmt;
+use std::process::Command;
+
+use clap::{Parser, ValueEnum};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::config_override::{ConfigOverride, ConfigOverrideValue};
+use crate::heading;
+
+/// Link time optimization setting of a profile
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, ValueEnum)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum Lto {
+    /// `lto = false`, thin LTO over the crate's own codegen units
+    #[value(name = "false")]
+    #[cfg_attr(feature = "serde", serde(rename = "false"))]
+    ThinLocal,
+    Thin,
+    /// Also accepted as `true`
+    #[value(alias = "true")]
+    Fat,
+    Off,
+}
+
+impl Lto {
+    /// The profile's `lto` value, `false` for thin local LTO
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Lto::ThinLocal => "false",
+            Lto::Thin => "thin",
+            Lto::Fat => "fat",
+            Lto::Off => "off",
+        }
+    }
+
+    fn config_value(self) -> ConfigOverrideValue {
+        match self {
+            Lto::ThinLocal => false.into(),
+            _ => self.as_str().into(),
+        }
+    }
+}
+
+impl fmt::Display for Lto {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Optimization level of a profile
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, ValueEnum)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub enum OptLevel {
+    #[value(name = "0")]
+    #[cfg_attr(feature = "serde", serde(rename = "0"))]
+    O0,
+    #[value(name = "1")]
+    #[cfg_attr(feature = "serde", serde(rename = "1"))]
+    O1,
+    #[value(name = "2")]
+    #[cfg_attr(feature = "serde", serde(rename = "2"))]
+    O2,
+    #[value(name = "3")]
+    #[cfg_attr(feature = "serde", serde(rename = "3"))]
+    O3,
+    /// Optimize for binary size
+    #[value(name = "s")]
+    #[cfg_attr(feature = "serde", serde(rename = "s"))]
+    Size,
+    /// Optimize for binary size, also turning off loop vectorization
+    #[value(name = "z")]
+    #[cfg_attr(feature = "serde", serde(rename = "z"))]
+    MinSize,
+}
+
+impl OptLevel {
+    /// The profile's `opt-level` value
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OptLevel::O0 => "0",
+            OptLevel::O1 => "1",
+            OptLevel::O2 => "2",
+            OptLevel::O3 => "3",
+            OptLevel::Size => "s",
+            OptLevel::MinSize => "z",
+        }
+    }
+
+    fn config_value(self) -> ConfigOverrideValue {
+        match self {
+            OptLevel::O0 => 0.into(),
+            OptLevel::O1 => 1.into(),
+            OptLevel::O2 => 2.into(),
+            OptLevel::O3 => 3.into(),
+            OptLevel::Size | OptLevel::MinSize => self.as_str().into(),
+        }
+    }
+}
+
+impl fmt::Display for OptLevel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Amount of debug info a profile generates
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, ValueEnum)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
+pub enum DebugInfo {
+    #[value(alias = "0", alias = "false")]
+    None,
+    LineDirectivesOnly,
+    LineTablesOnly,
+    #[value(alias = "1")]
+    Limited,
+    #[value(alias = "2", alias = "true")]
+    Full,
+}
+
+impl DebugInfo {
+    /// The profile's `debug` value
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DebugInfo::None => "none",
+            DebugInfo::LineDirectivesOnly => "line-directives-only",
+            DebugInfo::LineTablesOnly => "line-tables-only",
+            DebugInfo::Limited => "limited",
+            DebugInfo::Full => "full",
+        }
+    }
+}
+
+impl fmt::Display for DebugInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// What a profile strips from binaries
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, ValueEnum)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum Strip {
+    #[value(alias = "false")]
+    None,
+    Debuginfo,
+    #[value(alias = "true")]
+    Symbols,
+}
+
+impl Strip {
+    /// The profile's `strip` value
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Strip::None => "none",
+            Strip::Debuginfo => "debuginfo",
+            Strip::Symbols => "symbols",
+        }
+    }
+}
+
+impl fmt::Display for Strip {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Panic strategy of a profile
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, ValueEnum)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum PanicStrategy {
+    Unwind,
+    Abort,
+}
+
+impl PanicStrategy {
+    /// The profile's `panic` value
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PanicStrategy::Unwind => "unwind",
+            PanicStrategy::Abort => "abort",
+        }
+    }
+}
+
+impl fmt::Display for PanicStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Where a profile puts debug info
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, ValueEnum)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum SplitDebuginfo {
+    Off,
+    Packed,
+    Unpacked,
+}
+
+impl SplitDebuginfo {
+    /// The profile's `split-debuginfo` value
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SplitDebuginfo::Off => "off",
+            SplitDebuginfo::Packed => "packed",
+            SplitDebuginfo::Unpacked => "unpacked",
+        }
+    }
+}
+
+impl fmt::Display for SplitDebuginfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Per invocation overrides of the selected profile's settings,
+/// passed to cargo as `--config profile.<name>.<key>=...`
+///
+/// Flatten it into a command to get `--profile-lto` style options.
+#[derive(Clone, Debug, Default, Parser)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct ProfileOverrides {
+    /// Override the profile's link time optimization
+    #[arg(
+        long = "profile-lto",
+        value_name = "LTO",
+        help_heading = heading::PROFILE_OVERRIDES,
+    )]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub lto: Option<Lto>,
+
+    /// Override the profile's number of codegen units
+    #[arg(
+        long = "profile-codegen-units",
+        value_name = "N",
+        value_parser = clap::value_parser!(u32).range(1..),
+        help_heading = heading::PROFILE_OVERRIDES,
+    )]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub codegen_units: Option<u32>,
+
+    /// Override the profile's optimization level
+    #[arg(
+        long = "profile-opt-level",
+        value_name = "LEVEL",
+        help_heading = heading::PROFILE_OVERRIDES,
+    )]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub opt_level: Option<OptLevel>,
+
+    /// Override the profile's debug info level
+    #[arg(
+        long = "profile-debug",
+        value_name = "LEVEL",
+        help_heading = heading::PROFILE_OVERRIDES,
+    )]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub debug: Option<DebugInfo>,
+
+    /// Override what the profile strips from binaries
+    #[arg(
+        long = "profile-strip",
+        value_name = "STRIP",
+        help_heading = heading::PROFILE_OVERRIDES,
+    )]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub strip: Option<Strip>,
+
+    /// Override the profile's panic strategy
+    #[arg(
+        long = "profile-panic",
+        value_name = "STRATEGY",
+        help_heading = heading::PROFILE_OVERRIDES,
+    )]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub panic: Option<PanicStrategy>,
+
+    /// Override where the profile puts debug info
+    #[arg(
+        long = "profile-split-debuginfo",
+        value_name = "SPLIT",
+        help_heading = heading::PROFILE_OVERRIDES,
+    )]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub split_debuginfo: Option<SplitDebuginfo>,
+
+    /// Override whether the profile compiles incrementally
+    #[arg(
+        long = "profile-incremental",
+        value_name = "BOOL",
+        help_heading = heading::PROFILE_OVERRIDES,
+    )]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub incremental: Option<bool>,
+
+    /// Override whether the profile checks for integer overflow
+    #[arg(
+        long = "profile-overflow-checks",
+        value_name = "BOOL",
+        help_heading = heading::PROFILE_OVERRIDES,
+    )]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub overflow_checks: Option<bool>,
+}
+
+impl ProfileOverrides {
+    /// Whether no setting is overridden
+    pub fn is_empty(&self) -> bool {
+        self.lto.is_none()
+            && self.codegen_units.is_none()
+            && self.opt_level.is_none()
+            && self.debug.is_none()
+            && self.strip.is_none()
+            && self.panic.is_none()
+            && self.split_debuginfo.is_none()
+            && self.incremental.is_none()
+            && self.overflow_checks.is_none()
+    }
+
+    /// `--config` overrides of `profile`'s settings
+    pub fn config_overrides(&self, profile: &str) -> Vec<ConfigOverride> {
+        let settings: [(&str, Option<ConfigOverrideValue>); 9] = [
+            ("lto", self.lto.map(Lto::config_value)),
+            (
+                "codegen-units",
+                self.codegen_units.map(|units| i64::from(units).into()),
+            ),
+            ("opt-level", self.opt_level.map(OptLevel::config_value)),
+            ("debug", self.debug.map(|debug| debug.as_str().into())),
+            ("strip", self.strip.map(|strip| strip.as_str().into())),
+            ("panic", self.panic.map(|panic| panic.as_str().into())),
+            (
+                "split-debuginfo",
+                self.split_debuginfo.map(|split| split.as_str().into()),
+            ),
+            ("incremental", self.incremental.map(Into::into)),
+            ("overflow-checks", self.overflow_checks.map(Into::into)),
+        ];
+        settings
+            .into_iter()
+            .filter_map(|(setting, value)| Some(ConfigOverride::profile(profile, setting, value?)))
+            .collect()
+    }
+
+    /// Apply overrides of `profile`'s settings to `Command`,
+    /// see [`CommonOptions::profile_name`](crate::CommonOptions::profile_name)
+    pub fn apply(&self, cmd: &mut Command, profile: &str) {
+        for config in self.config_overrides(profile) {
+            cmd.arg("--config").arg(config.to_string());
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{DebugInfo, Lto, OptLevel, ProfileOverrides};
+    use crate::Build;
+    use clap::{CommandFactory, Parser};
+
+    #[derive(Debug, Parser)]
+    struct Custom {
+        #[command(flatten)]
+        build: Build,
+
+        #[command(flatten)]
+        profile_overrides: ProfileOverrides,
+    }
+
+    #[test]
+    fn verify_cli() {
+        <Custom as CommandFactory>::command().debug_assert()
+    }
+
+    #[test]
+    fn config_overrides() {
+        let custom = Custom::parse_from([
+            "custom",
+            "--release",
+            "--profile-lto",
+            "true",
+            "--profile-opt-level",
+            "z",
+            "--profile-debug",
+            "1",
+            "--profile-codegen-units",
+            "1",
+            "--profile-incremental",
+            "false",
+        ]);
+        let overrides = &custom.profile_overrides;
+        assert_eq!(overrides.lto, Some(Lto::Fat));
+        assert_eq!(overrides.opt_level, Some(OptLevel::MinSize));
+        assert_eq!(overrides.debug, Some(DebugInfo::Limited));
+
+        let profile = custom
+            .build
+            .profile_name(custom.build.compilation_options.release);
+        assert_eq!(profile, "release");
+        let mut cmd = custom.build.command();
+        overrides.apply(&mut cmd, profile);
+        let args: Vec<_> = cmd.get_args().map(|a| a.to_owned()).collect();
+        assert_eq!(
+            args,
+            [
+                "build",
+                "--release",
+                "--config",
+                "profile.release.lto=\"fat\"",
+                "--config",
+                "profile.release.codegen-units=1",
+                "--config",
+                "profile.release.opt-level=\"z\"",
+                "--config",
+                "profile.release.debug=\"limited\"",
+                "--config",
+                "profile.release.incremental=false",
+            ]
+        );
+
+        let overrides = ProfileOverrides {
+            lto: Some(Lto::ThinLocal),
+            opt_level: Some(OptLevel::O2),
+            ..Default::default()
+        };
+        let configs: Vec<_> = overrides
+            .config_overrides("dev")
+            .iter()
+            .map(|c| c.to_string())
+            .collect();
+        assert_eq!(
+            configs,
+            ["profile.dev.lto=false", "profile.dev.opt-level=2"]
+        );
+        assert!(ProfileOverrides::default().is_empty());
+        assert!(Custom::try_parse_from(["custom", "--profile-codegen-units", "0"]).is_err());
+        assert!(Custom::try_parse_from(["custom", "--profile-panic", "exit"]).is_err());
+    }
+}
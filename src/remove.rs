@@ -0,0 +1,121 @@
+use std::ops::{Deref, DerefMut};
+use std::path::PathBuf;
+use std::process::Command;
+
+use clap::{ArgAction, Parser};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::common::{CommonOptions, GlobalOptions};
+use crate::heading;
+
+/// Remove dependencies from a Cargo.toml manifest file
+#[derive(Clone, Debug, Default, Parser)]
+#[command(
+    display_order = 1,
+    after_help = "Run `cargo help remove` for more detailed information."
+)]
+#[group(skip)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct Remove {
+    #[command(flatten)]
+    #[cfg_attr(feature = "serde", serde(flatten))]
+    pub global_options: GlobalOptions,
+
+    /// Don't actually write the manifest
+    #[arg(short = 'n', long)]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub dry_run: bool,
+
+    /// Package to remove from
+    #[arg(
+        short = 'p',
+        long,
+        value_name = "SPEC",
+        num_args=0..=1,
+        help_heading = heading::PACKAGE_SELECTION,
+    )]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub package: Option<String>,
+
+    /// Remove from dev-dependencies
+    #[arg(long, conflicts_with = "build", help_heading = heading::SECTION)]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub dev: bool,
+
+    /// Remove from build-dependencies
+    #[arg(long, help_heading = heading::SECTION)]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub build: bool,
+
+    /// Remove from target-dependencies
+    #[arg(long, value_name = "TARGET", help_heading = heading::SECTION)]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub target: Option<String>,
+
+    /// Path to Cargo.toml
+    #[arg(long, value_name = "PATH", help_heading = heading::MANIFEST_OPTIONS)]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub manifest_path: Option<PathBuf>,
+
+    /// Dependencies to be removed
+    #[arg(value_name = "DEP_ID", action = ArgAction::Append, num_args = 1.., required = true)]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub dependencies: Vec<String>,
+}
+
+impl Remove {
+    /// Build a `cargo remove` command
+    pub fn command(&self) -> Command {
+        let mut cmd = CommonOptions::cargo_command();
+        self.global_options.apply_top_level(&mut cmd);
+        cmd.arg("remove");
+        self.global_options.apply(&mut cmd);
+        if self.dry_run {
+            cmd.arg("--dry-run");
+        }
+        if let Some(package) = self.package.as_ref() {
+            cmd.arg("--package").arg(package);
+        }
+        if self.dev {
+            cmd.arg("--dev");
+        }
+        if self.build {
+            cmd.arg("--build");
+        }
+        if let Some(target) = self.target.as_ref() {
+            cmd.arg("--target").arg(target);
+        }
+        if let Some(path) = self.manifest_path.as_ref() {
+            cmd.arg("--manifest-path").arg(path);
+        }
+        cmd.args(&self.dependencies);
+        cmd
+    }
+}
+
+impl Deref for Remove {
+    type Target = GlobalOptions;
+
+    fn deref(&self) -> &Self::Target {
+        &self.global_options
+    }
+}
+
+impl DerefMut for Remove {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.global_options
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Remove;
+    use clap::CommandFactory;
+
+    #[test]
+    fn verify_cli() {
+        <Remove as CommandFactory>::command().debug_assert()
+    }
+}
@@ -0,0 +1,300 @@
+use std::io;
+use std::ops::{Deref, DerefMut};
+use std::process::Command;
+use std::str::FromStr;
+
+use clap::Parser;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::common::{self, CommonOptions, GlobalOptions};
+use crate::heading;
+
+/// Reports any crates which will eventually stop compiling
+#[derive(Clone, Debug, Default, Parser)]
+#[command(
+    display_order = 1,
+    after_help = "Run `cargo help report` for more detailed information."
+)]
+#[group(skip)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct Report {
+    #[command(flatten)]
+    #[cfg_attr(feature = "serde", serde(flatten))]
+    pub global_options: GlobalOptions,
+
+    /// Identifier of the report generated by a Cargo command invocation
+    #[arg(long, value_name = "id")]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub id: Option<u32>,
+
+    /// Package to display a report for
+    #[arg(
+        short = 'p',
+        long,
+        value_name = "SPEC",
+        num_args=0..=1,
+        help_heading = heading::PACKAGE_SELECTION,
+    )]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub package: Option<String>,
+}
+
+impl Report {
+    /// Build a `cargo report future-incompatibilities` command
+    pub fn command(&self) -> Command {
+        let mut cmd = CommonOptions::cargo_command();
+        self.global_options.apply_top_level(&mut cmd);
+        cmd.arg("report").arg("future-incompatibilities");
+        self.global_options.apply(&mut cmd);
+        if let Some(id) = self.id {
+            cmd.arg("--id").arg(id.to_string());
+        }
+        if let Some(package) = self.package.as_ref() {
+            cmd.arg("--package").arg(package);
+        }
+        cmd
+    }
+
+    /// Run `cargo report future-incompatibilities` and parse its output
+    ///
+    /// Pass `--color never` if colored output is configured, the parser
+    /// expects plain text.
+    pub fn future_incompat_report(&self) -> io::Result<FutureIncompatReport> {
+        let stdout = common::capture_stdout(&mut self.command())?;
+        stdout.parse()
+    }
+}
+
+impl Deref for Report {
+    type Target = GlobalOptions;
+
+    fn deref(&self) -> &Self::Target {
+        &self.global_options
+    }
+}
+
+impl DerefMut for Report {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.global_options
+    }
+}
+
+/// Parsed output of `cargo report future-incompatibilities`
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct FutureIncompatReport {
+    /// Packages triggering future incompatibility lints, in report order
+    pub packages: Vec<FutureIncompatPackage>,
+}
+
+/// A package listed in a future incompatibility report
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct FutureIncompatPackage {
+    /// Package name
+    pub name: String,
+    /// Package version
+    pub version: String,
+    /// Diagnostics reported for this package
+    pub items: Vec<FutureIncompatItem>,
+}
+
+/// A single future incompatibility diagnostic
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct FutureIncompatItem {
+    /// Lint name, when the diagnostic mentions it, e.g. `proc_macro_back_compat`
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub lint: Option<String>,
+    /// Primary message, e.g. `using procedural-masquerade crate`
+    pub message: String,
+    /// The diagnostic as rendered by rustc
+    pub rendered: String,
+}
+
+impl FutureIncompatReport {
+    /// Whether no package triggered any future incompatibility lint
+    pub fn is_empty(&self) -> bool {
+        self.packages.iter().all(|package| package.items.is_empty())
+    }
+
+    /// Entries not present in `baseline`, e.g. the report of the main branch.
+    ///
+    /// Diagnostics are matched by package name, lint and message, so neither
+    /// version bumps nor moved source locations count as new entries.
+    pub fn new_since(&self, baseline: &FutureIncompatReport) -> FutureIncompatReport {
+        let known = |name: &str, item: &FutureIncompatItem| {
+            baseline.packages.iter().any(|package| {
+                package.name == name
+                    && package
+                        .items
+                        .iter()
+                        .any(|old| old.lint == item.lint && old.message == item.message)
+            })
+        };
+        let packages = self
+            .packages
+            .iter()
+            .filter_map(|package| {
+                let items: Vec<_> = package
+                    .items
+                    .iter()
+                    .filter(|item| !known(&package.name, item))
+                    .cloned()
+                    .collect();
+                (!items.is_empty()).then(|| FutureIncompatPackage {
+                    items,
+                    ..package.clone()
+                })
+            })
+            .collect();
+        FutureIncompatReport { packages }
+    }
+}
+
+impl FromStr for FutureIncompatReport {
+    type Err = io::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut packages: Vec<FutureIncompatPackage> = Vec::new();
+        for line in s.lines() {
+            if let Some(rest) = line.strip_prefix("The package `") {
+                // Path and git packages are followed by their source, e.g.
+                // `foo v0.1.0 (/work/foo)`
+                let (name, version) = rest
+                    .split_once('`')
+                    .and_then(|(id, _)| id.split_once(" v"))
+                    .and_then(|(name, rest)| Some((name, rest.split_whitespace().next()?)))
+                    .ok_or_else(|| {
+                        io::Error::new(
+                            io::ErrorKind::InvalidData,
+                            format!("unexpected package line `{}`", line),
+                        )
+                    })?;
+                packages.push(FutureIncompatPackage {
+                    name: name.to_string(),
+                    version: version.to_string(),
+                    items: Vec::new(),
+                });
+                continue;
+            }
+            let Some(package) = packages.last_mut() else {
+                continue;
+            };
+            let Some(diagnostic) = line.strip_prefix('>') else {
+                continue;
+            };
+            let diagnostic = diagnostic.strip_prefix(' ').unwrap_or(diagnostic);
+            let message = diagnostic
+                .strip_prefix("warning: ")
+                .or_else(|| diagnostic.strip_prefix("error: "));
+            match (message, package.items.last_mut()) {
+                (Some(message), _) => package.items.push(FutureIncompatItem {
+                    lint: None,
+                    message: message.to_string(),
+                    rendered: diagnostic.to_string(),
+                }),
+                (None, Some(item)) => {
+                    item.rendered.push('\n');
+                    item.rendered.push_str(diagnostic);
+                }
+                (None, None) => {}
+            }
+        }
+        for item in packages.iter_mut().flat_map(|package| &mut package.items) {
+            item.rendered.truncate(item.rendered.trim_end().len());
+            item.lint = lint_name(&item.rendered);
+        }
+        Ok(Self { packages })
+    }
+}
+
+/// Find the lint name in a `#[warn(lint_name)]` style note
+fn lint_name(rendered: &str) -> Option<String> {
+    ["#[warn(", "#[deny(", "#[forbid(", "#[allow("]
+        .iter()
+        .find_map(|prefix| {
+            let (_, rest) = rendered.split_once(prefix)?;
+            let (lint, _) = rest.split_once(")]")?;
+            Some(lint.to_string())
+        })
+}
+
+#[cfg(test)]
+mod test {
+    use super::{FutureIncompatReport, Report};
+    use clap::CommandFactory;
+
+    #[test]
+    fn verify_cli() {
+        <Report as CommandFactory>::command().debug_assert()
+    }
+
+    #[test]
+    fn parse_future_incompat_report() {
+        let output = r#"The following warnings were discovered during the build. These warnings are an
+indication that the packages contain code that will become an error in a
+future release of Rust.
+
+The package `rental v0.5.6` currently triggers the following future incompatibility lints:
+> warning: using `procedural-masquerade` crate
+>   --> /home/user/.cargo/registry/src/rental-impl-0.5.5/src/lib.rs:1:1
+>    |
+>    = note: `#[warn(proc_macro_back_compat)]` on by default
+>    = warning: this was previously accepted by the compiler but is being phased out
+>
+> warning: never type fallback affects this call to an `unsafe` function
+>    |
+>    = note: `#[warn(never_type_fallback_flowing_into_unsafe)]` on by default
+>
+
+The package `old-macro v1.0.0` currently triggers the following future incompatibility lints:
+> warning: trailing semicolon in macro used in expression position
+>
+
+The package `local-macro v0.1.0 (/work/local-macro)` currently triggers the following future incompatibility lints:
+> warning: trailing semicolon in macro used in expression position
+>
+
+The package `git-macro v0.2.0 (https://github.com/user/git-macro#0a1b2c3d)` currently triggers the following future incompatibility lints:
+> warning: trailing semicolon in macro used in expression position
+>
+"#;
+        let report: FutureIncompatReport = output.parse().unwrap();
+        assert_eq!(report.packages.len(), 4);
+        let rental = &report.packages[0];
+        assert_eq!(
+            (rental.name.as_str(), rental.version.as_str()),
+            ("rental", "0.5.6")
+        );
+        assert_eq!(rental.items.len(), 2);
+        assert_eq!(
+            rental.items[0].lint.as_deref(),
+            Some("proc_macro_back_compat")
+        );
+        assert_eq!(
+            rental.items[0].message,
+            "using `procedural-masquerade` crate"
+        );
+        assert!(rental.items[0].rendered.ends_with("being phased out"));
+        assert_eq!(report.packages[1].items[0].lint, None);
+        let sources: Vec<_> = report.packages[2..]
+            .iter()
+            .map(|p| (p.name.as_str(), p.version.as_str()))
+            .collect();
+        assert_eq!(sources, [("local-macro", "0.1.0"), ("git-macro", "0.2.0")]);
+
+        let mut baseline = report.clone();
+        baseline.packages[0].items.pop();
+        baseline.packages[0].version = "0.5.5".to_string();
+        let new = report.new_since(&baseline);
+        assert_eq!(new.packages.len(), 1);
+        assert_eq!(
+            new.packages[0].items[0].lint.as_deref(),
+            Some("never_type_fallback_flowing_into_unsafe")
+        );
+        assert!(report.new_since(&report).is_empty());
+    }
+}
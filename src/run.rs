@@ -1,5 +1,5 @@
+use std::io;
 use std::ops::{Deref, DerefMut};
-use std::path::PathBuf;
 use std::process::Command;
 
 use clap::{ArgAction, Parser};
@@ -7,8 +7,9 @@ use clap::{ArgAction, Parser};
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
-use crate::common::CommonOptions;
+use crate::common::{CommonOptions, CompilationOptions, ManifestOptions};
 use crate::heading;
+use crate::CargoVersion;
 
 /// Run a binary or example of the local package
 #[derive(Clone, Debug, Default, Parser)]
@@ -23,25 +24,13 @@ pub struct Run {
     #[cfg_attr(feature = "serde", serde(flatten))]
     pub common: CommonOptions,
 
-    /// Path to Cargo.toml
-    #[arg(long, value_name = "PATH", help_heading = heading::MANIFEST_OPTIONS)]
-    #[cfg_attr(feature = "serde", serde(default))]
-    pub manifest_path: Option<PathBuf>,
-
-    /// Build artifacts in release mode, with optimizations
-    #[arg(short = 'r', long, help_heading = heading::COMPILATION_OPTIONS)]
-    #[cfg_attr(feature = "serde", serde(default))]
-    pub release: bool,
-
-    /// Ignore `rust-version` specification in packages
-    #[arg(long)]
-    #[cfg_attr(feature = "serde", serde(default))]
-    pub ignore_rust_version: bool,
+    #[command(flatten)]
+    #[cfg_attr(feature = "serde", serde(flatten))]
+    pub compilation_options: CompilationOptions,
 
-    /// Output build graph in JSON (unstable)
-    #[arg(long, help_heading = heading::COMPILATION_OPTIONS)]
-    #[cfg_attr(feature = "serde", serde(default))]
-    pub unit_graph: bool,
+    #[command(flatten)]
+    #[cfg_attr(feature = "serde", serde(flatten))]
+    pub manifest_options: ManifestOptions,
 
     /// Package to run (see `cargo help pkgid`)
     #[arg(
@@ -87,22 +76,14 @@ impl Run {
     /// Build a `cargo run` command
     pub fn command(&self) -> Command {
         let mut cmd = CommonOptions::cargo_command();
+        self.common.global_options.apply_top_level(&mut cmd);
         cmd.arg("run");
 
-        self.common.apply(&mut cmd);
+        self.common
+            .apply_with_unstable(&mut cmd, &self.unstable_options());
 
-        if let Some(path) = self.manifest_path.as_ref() {
-            cmd.arg("--manifest-path").arg(path);
-        }
-        if self.release {
-            cmd.arg("--release");
-        }
-        if self.ignore_rust_version {
-            cmd.arg("--ignore-rust-version");
-        }
-        if self.unit_graph {
-            cmd.arg("--unit-graph");
-        }
+        self.compilation_options.apply(&mut cmd);
+        self.manifest_options.apply(&mut cmd);
         for pkg in &self.packages {
             cmd.arg("--package").arg(pkg);
         }
@@ -119,6 +100,28 @@ impl Run {
 
         cmd
     }
+
+    /// Build a `cargo run` command that `version` understands
+    ///
+    /// `-Z unstable-options` is added when an option is unstable on `version`
+    /// and options `version` doesn't have (anymore) are an error.
+    pub fn command_for(&self, version: &CargoVersion) -> io::Result<Command> {
+        let mut run = self.clone();
+        run.common.adapt_to_version(version)?;
+        Ok(run.command())
+    }
+
+    /// Unstable options in use, cargo only takes them with `-Z unstable-options`
+    pub fn unstable_options(&self) -> Vec<&'static str> {
+        let mut options = self.common.unstable_options();
+        options.extend(self.compilation_options.unstable_options());
+        options
+    }
+
+    /// Whether the options only work on a nightly cargo
+    pub fn requires_nightly(&self) -> bool {
+        self.common.requires_nightly_for(&self.unstable_options())
+    }
 }
 
 impl Deref for Run {
@@ -1,5 +1,5 @@
+use std::io;
 use std::ops::{Deref, DerefMut};
-use std::path::PathBuf;
 use std::process::Command;
 
 use clap::{ArgAction, Parser};
@@ -7,8 +7,9 @@ use clap::{ArgAction, Parser};
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
-use crate::common::CommonOptions;
+use crate::common::{CommonOptions, CompilationOptions, ManifestOptions, TargetSelection};
 use crate::heading;
+use crate::CargoVersion;
 
 /// Compile a package, and pass extra options to the compiler
 #[derive(Clone, Debug, Default, Parser)]
@@ -23,25 +24,17 @@ pub struct Rustc {
     #[cfg_attr(feature = "serde", serde(flatten))]
     pub common: CommonOptions,
 
-    /// Path to Cargo.toml
-    #[arg(long, value_name = "PATH", help_heading = heading::MANIFEST_OPTIONS)]
-    #[cfg_attr(feature = "serde", serde(default))]
-    pub manifest_path: Option<PathBuf>,
-
-    /// Build artifacts in release mode, with optimizations
-    #[arg(short = 'r', long, help_heading = heading::COMPILATION_OPTIONS)]
-    #[cfg_attr(feature = "serde", serde(default))]
-    pub release: bool,
+    #[command(flatten)]
+    #[cfg_attr(feature = "serde", serde(flatten))]
+    pub compilation_options: CompilationOptions,
 
-    /// Ignore `rust-version` specification in packages
-    #[arg(long)]
-    #[cfg_attr(feature = "serde", serde(default))]
-    pub ignore_rust_version: bool,
+    #[command(flatten)]
+    #[cfg_attr(feature = "serde", serde(flatten))]
+    pub manifest_options: ManifestOptions,
 
-    /// Output build graph in JSON (unstable)
-    #[arg(long, help_heading = heading::COMPILATION_OPTIONS)]
-    #[cfg_attr(feature = "serde", serde(default))]
-    pub unit_graph: bool,
+    #[command(flatten)]
+    #[cfg_attr(feature = "serde", serde(flatten))]
+    pub target_selection: TargetSelection,
 
     /// Package to build (see `cargo help pkgid`)
     #[arg(
@@ -55,78 +48,6 @@ pub struct Rustc {
     #[cfg_attr(feature = "serde", serde(default))]
     pub packages: Vec<String>,
 
-    /// Build only this package's library
-    #[arg(long, help_heading = heading::TARGET_SELECTION)]
-    #[cfg_attr(feature = "serde", serde(default))]
-    pub lib: bool,
-
-    /// Build only the specified binary
-    #[arg(
-        long,
-        value_name = "NAME",
-        action = ArgAction::Append,
-        num_args=0..=1,
-        help_heading = heading::TARGET_SELECTION,
-    )]
-    #[cfg_attr(feature = "serde", serde(default))]
-    pub bin: Vec<String>,
-
-    /// Build all binaries
-    #[arg(long)]
-    #[cfg_attr(feature = "serde", serde(default))]
-    pub bins: bool,
-
-    /// Build only the specified example
-    #[arg(
-        long,
-        value_name = "NAME",
-        action = ArgAction::Append,
-        num_args=0..=1,
-        help_heading = heading::TARGET_SELECTION,
-    )]
-    #[cfg_attr(feature = "serde", serde(default))]
-    pub example: Vec<String>,
-
-    /// Build all examples
-    #[arg(long, help_heading = heading::TARGET_SELECTION)]
-    #[cfg_attr(feature = "serde", serde(default))]
-    pub examples: bool,
-
-    /// Build only the specified test target
-    #[arg(
-        long,
-        value_name = "NAME",
-        action = ArgAction::Append,
-        help_heading = heading::TARGET_SELECTION,
-    )]
-    #[cfg_attr(feature = "serde", serde(default))]
-    pub test: Vec<String>,
-
-    /// Build all tests
-    #[arg(long, help_heading = heading::TARGET_SELECTION)]
-    #[cfg_attr(feature = "serde", serde(default))]
-    pub tests: bool,
-
-    /// Build only the specified bench target
-    #[arg(
-        long,
-        value_name = "NAME",
-        action = ArgAction::Append,
-        help_heading = heading::TARGET_SELECTION,
-    )]
-    #[cfg_attr(feature = "serde", serde(default))]
-    pub bench: Vec<String>,
-
-    /// Build all benches
-    #[arg(long, help_heading = heading::TARGET_SELECTION)]
-    #[cfg_attr(feature = "serde", serde(default))]
-    pub benches: bool,
-
-    /// Build all targets
-    #[arg(long, help_heading = heading::TARGET_SELECTION)]
-    #[cfg_attr(feature = "serde", serde(default))]
-    pub all_targets: bool,
-
     /// Output compiler information without compiling
     #[arg(long, value_name = "INFO")]
     #[cfg_attr(feature = "serde", serde(default))]
@@ -152,55 +73,18 @@ impl Rustc {
     /// Build a `cargo rustc` command
     pub fn command(&self) -> Command {
         let mut cmd = CommonOptions::cargo_command();
+        self.common.global_options.apply_top_level(&mut cmd);
         cmd.arg("rustc");
 
-        self.common.apply(&mut cmd);
+        self.common
+            .apply_with_unstable(&mut cmd, &self.unstable_options());
 
-        if let Some(path) = self.manifest_path.as_ref() {
-            cmd.arg("--manifest-path").arg(path);
-        }
-        if self.release {
-            cmd.arg("--release");
-        }
-        if self.ignore_rust_version {
-            cmd.arg("--ignore-rust-version");
-        }
-        if self.unit_graph {
-            cmd.arg("--unit-graph");
-        }
+        self.compilation_options.apply(&mut cmd);
+        self.manifest_options.apply(&mut cmd);
+        self.target_selection.apply(&mut cmd);
         for pkg in &self.packages {
             cmd.arg("--package").arg(pkg);
         }
-        if self.lib {
-            cmd.arg("--lib");
-        }
-        for bin in &self.bin {
-            cmd.arg("--bin").arg(bin);
-        }
-        if self.bins {
-            cmd.arg("--bins");
-        }
-        for example in &self.example {
-            cmd.arg("--example").arg(example);
-        }
-        if self.examples {
-            cmd.arg("--examples");
-        }
-        for test in &self.test {
-            cmd.arg("--test").arg(test);
-        }
-        if self.tests {
-            cmd.arg("--tests");
-        }
-        for bench in &self.bench {
-            cmd.arg("--bench").arg(bench);
-        }
-        if self.benches {
-            cmd.arg("--benches");
-        }
-        if self.all_targets {
-            cmd.arg("--all-targets");
-        }
         if let Some(print) = self.print.as_ref() {
             cmd.arg("--print").arg(print);
         }
@@ -216,6 +100,28 @@ impl Rustc {
 
         cmd
     }
+
+    /// Build a `cargo rustc` command that `version` understands
+    ///
+    /// `-Z unstable-options` is added when an option is unstable on `version`
+    /// and options `version` doesn't have (anymore) are an error.
+    pub fn command_for(&self, version: &CargoVersion) -> io::Result<Command> {
+        let mut rustc = self.clone();
+        rustc.common.adapt_to_version(version)?;
+        Ok(rustc.command())
+    }
+
+    /// Unstable options in use, cargo only takes them with `-Z unstable-options`
+    pub fn unstable_options(&self) -> Vec<&'static str> {
+        let mut options = self.common.unstable_options();
+        options.extend(self.compilation_options.unstable_options());
+        options
+    }
+
+    /// Whether the options only work on a nightly cargo
+    pub fn requires_nightly(&self) -> bool {
+        self.common.requires_nightly_for(&self.unstable_options())
+    }
 }
 
 impl Deref for Rustc {
@@ -0,0 +1,224 @@
+use std::fmt;
+use std::io;
+use std::ops::{Deref, DerefMut};
+use std::path::PathBuf;
+use std::process::Command;
+
+use clap::{ArgAction, Parser, ValueEnum};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::common::{self, CommonOptions, CompilationOptions, ManifestOptions, TargetSelection};
+use crate::heading;
+use crate::CargoVersion;
+
+/// The output type `cargo rustdoc` writes
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, ValueEnum)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum OutputFormat {
+    Html,
+    Json,
+}
+
+impl OutputFormat {
+    /// Value passed to `--output-format`
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OutputFormat::Html => "html",
+            OutputFormat::Json => "json",
+        }
+    }
+}
+
+impl fmt::Display for OutputFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Build a package's documentation, using specified custom flags.
+#[derive(Clone, Debug, Default, Parser)]
+#[command(
+    display_order = 1,
+    after_help = "Run `cargo help rustdoc` for more detailed information."
+)]
+#[group(skip)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct Rustdoc {
+    #[command(flatten)]
+    #[cfg_attr(feature = "serde", serde(flatten))]
+    pub common: CommonOptions,
+
+    #[command(flatten)]
+    #[cfg_attr(feature = "serde", serde(flatten))]
+    pub compilation_options: CompilationOptions,
+
+    #[command(flatten)]
+    #[cfg_attr(feature = "serde", serde(flatten))]
+    pub manifest_options: ManifestOptions,
+
+    #[command(flatten)]
+    #[cfg_attr(feature = "serde", serde(flatten))]
+    pub target_selection: TargetSelection,
+
+    /// Package to document
+    #[arg(
+        short = 'p',
+        long = "package",
+        value_name = "SPEC",
+        action = ArgAction::Append,
+        num_args=0..=1,
+        help_heading = heading::PACKAGE_SELECTION,
+    )]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub packages: Vec<String>,
+
+    /// Opens the docs in a browser after the operation
+    #[arg(long)]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub open: bool,
+
+    /// The output type to write (unstable)
+    #[arg(long, value_name = "FMT")]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub output_format: Option<OutputFormat>,
+
+    /// Extra rustdoc flags
+    #[arg(value_name = "args", trailing_var_arg = true, num_args = 0..)]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub args: Vec<String>,
+}
+
+impl Rustdoc {
+    /// Build a `cargo rustdoc` command
+    pub fn command(&self) -> Command {
+        let mut cmd = CommonOptions::cargo_command();
+        self.common.global_options.apply_top_level(&mut cmd);
+        cmd.arg("rustdoc");
+
+        self.common
+            .apply_with_unstable(&mut cmd, &self.unstable_options());
+
+        self.compilation_options.apply(&mut cmd);
+        self.manifest_options.apply(&mut cmd);
+        self.target_selection.apply(&mut cmd);
+        for pkg in &self.packages {
+            cmd.arg("--package").arg(pkg);
+        }
+        if self.open {
+            cmd.arg("--open");
+        }
+        if let Some(format) = self.output_format {
+            cmd.arg("--output-format").arg(format.as_str());
+        }
+        if !self.args.is_empty() {
+            cmd.arg("--").args(&self.args);
+        }
+
+        cmd
+    }
+
+    /// Path of the rustdoc JSON file written for `crate_name`
+    /// when `output_format` is `json`.
+    ///
+    /// The target directory is taken from `--target-dir`, then
+    /// `CARGO_TARGET_DIR`/`CARGO_BUILD_TARGET_DIR`, and finally falls back to
+    /// `target` next to `--manifest-path` (or the current directory).
+    /// That fallback does not know about workspace roots or `build.target-dir`
+    /// set in config files, pass `--target-dir` when those matter.
+    pub fn json_output_path(&self, crate_name: &str) -> PathBuf {
+        let mut path = common::target_dir(
+            self.common.target_dir.as_deref(),
+            self.manifest_options.manifest_path.as_deref(),
+        );
+        if let Some(target) = self.common.target.first() {
+            path.push(common::rust_target(target));
+        }
+        path.push("doc");
+        path.push(format!("{}.json", crate_name.replace('-', "_")));
+        path
+    }
+
+    /// Build a `cargo rustdoc` command that `version` understands
+    ///
+    /// `-Z unstable-options` is added when an option is unstable on `version`
+    /// and options `version` doesn't have (anymore) are an error.
+    pub fn command_for(&self, version: &CargoVersion) -> io::Result<Command> {
+        let mut rustdoc = self.clone();
+        rustdoc.common.adapt_to_version(version)?;
+        Ok(rustdoc.command())
+    }
+
+    /// Unstable options in use, cargo only takes them with `-Z unstable-options`
+    pub fn unstable_options(&self) -> Vec<&'static str> {
+        let mut options = self.common.unstable_options();
+        options.extend(self.compilation_options.unstable_options());
+        if self.output_format.is_some() {
+            options.push("--output-format");
+        }
+        options
+    }
+
+    /// Whether the options only work on a nightly cargo
+    pub fn requires_nightly(&self) -> bool {
+        self.common.requires_nightly_for(&self.unstable_options())
+    }
+}
+
+impl Deref for Rustdoc {
+    type Target = CommonOptions;
+
+    fn deref(&self) -> &Self::Target {
+        &self.common
+    }
+}
+
+impl DerefMut for Rustdoc {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.common
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{OutputFormat, Rustdoc};
+    use clap::{CommandFactory, Parser};
+    use std::path::Path;
+
+    #[test]
+    fn verify_cli() {
+        <Rustdoc as CommandFactory>::command().debug_assert()
+    }
+
+    #[test]
+    fn json_output() {
+        let rustdoc = Rustdoc::parse_from([
+            "rustdoc",
+            "-Zunstable-options",
+            "--output-format",
+            "json",
+            "--target-dir",
+            "out",
+            "--target",
+            "x86_64-unknown-linux-gnu.2.17",
+            "--",
+            "--cfg",
+            "docsrs",
+        ]);
+        assert_eq!(rustdoc.output_format, Some(OutputFormat::Json));
+        assert_eq!(
+            rustdoc.json_output_path("cargo-options"),
+            Path::new("out/x86_64-unknown-linux-gnu/doc/cargo_options.json")
+        );
+        let args: Vec<_> = rustdoc.command().get_args().map(|a| a.to_owned()).collect();
+        assert!(args.ends_with(&[
+            "--output-format".into(),
+            "json".into(),
+            "--".into(),
+            "--cfg".into(),
+            "docsrs".into()
+        ]));
+    }
+}
@@ -0,0 +1,261 @@
+use std::env;
+use std::ffi::{OsStr, OsString};
+use std::process::Command;
+
+use crate::ConfigOverride;
+
+/// Extra rustc or rustdoc flags, added to the ones cargo would otherwise use
+///
+/// cargo takes flags from the first of these that is set, without merging:
+///
+/// 1. `CARGO_ENCODED_RUSTFLAGS`, split on `\x1f`
+/// 2. `RUSTFLAGS`, split on whitespace
+/// 3. `target.<triple>.rustflags` and `target.<cfg>.rustflags` from config
+/// 4. `build.rustflags` from config, `CARGO_BUILD_RUSTFLAGS` sets this one
+///
+/// with the `RUSTDOCFLAGS` equivalents for rustdoc. When 1 or 2 is set, on
+/// the `Command` or in the current environment, [`RustFlags::apply`] appends
+/// the added flags and writes the result to `CARGO_ENCODED_RUSTFLAGS`.
+/// Otherwise they are passed as `--config build.rustflags=[...]`, which cargo
+/// merges with `build.rustflags` from config files and `CARGO_BUILD_RUSTFLAGS`.
+/// Like those, they are ignored when config sets `target.<triple>.rustflags`,
+/// extend that with a [`ConfigOverride`] instead.
+///
+/// ```
+/// use cargo_options::{Build, RustFlags};
+///
+/// let mut cmd = Build::default().command();
+/// RustFlags::new()
+///     .target_cpu("native")
+///     .cfg("tokio_unstable")
+///     .apply(&mut cmd);
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RustFlags {
+    /// Flags added after the inherited ones
+    pub flags: Vec<String>,
+    rustdoc: bool,
+}
+
+impl RustFlags {
+    /// Flags for rustc
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Flags for rustdoc
+    pub fn rustdoc() -> Self {
+        RustFlags {
+            flags: Vec::new(),
+            rustdoc: true,
+        }
+    }
+
+    /// Add a single flag, spaces in it are kept as is
+    pub fn arg(&mut self, flag: impl Into<String>) -> &mut Self {
+        self.flags.push(flag.into());
+        self
+    }
+
+    /// Add flags
+    pub fn args<I, S>(&mut self, flags: I) -> &mut Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.flags.extend(flags.into_iter().map(Into::into));
+        self
+    }
+
+    /// Add `-C <option>=<value>`
+    pub fn codegen(&mut self, option: &str, value: &str) -> &mut Self {
+        self.args(["-C".to_string(), format!("{}={}", option, value)])
+    }
+
+    /// Add `-C target-cpu=<cpu>`
+    pub fn target_cpu(&mut self, cpu: &str) -> &mut Self {
+        self.codegen("target-cpu", cpu)
+    }
+
+    /// Add `-C link-arg=<arg>`
+    pub fn link_arg(&mut self, arg: &str) -> &mut Self {
+        self.codegen("link-arg", arg)
+    }
+
+    /// Add `--cfg <cfg>`, e.g. `feature="foo"`
+    pub fn cfg(&mut self, cfg: &str) -> &mut Self {
+        self.args(["--cfg", cfg])
+    }
+
+    /// Add `-A <lint>`
+    pub fn allow(&mut self, lint: &str) -> &mut Self {
+        self.args(["-A", lint])
+    }
+
+    /// Add `-W <lint>`
+    pub fn warn(&mut self, lint: &str) -> &mut Self {
+        self.args(["-W", lint])
+    }
+
+    /// Add `-D <lint>`, e.g. `warnings`
+    pub fn deny(&mut self, lint: &str) -> &mut Self {
+        self.args(["-D", lint])
+    }
+
+    /// Environment variables cargo reads the flags from, by precedence
+    fn vars(&self) -> [&'static str; 2] {
+        if self.rustdoc {
+            ["CARGO_ENCODED_RUSTDOCFLAGS", "RUSTDOCFLAGS"]
+        } else {
+            ["CARGO_ENCODED_RUSTFLAGS", "RUSTFLAGS"]
+        }
+    }
+
+    /// Flags inherited from `CARGO_ENCODED_RUSTFLAGS` or `RUSTFLAGS` in the
+    /// environment of the current process
+    pub fn inherited(&self) -> Vec<String> {
+        self.inherited_from(|var| env::var(var).ok())
+            .unwrap_or_default()
+    }
+
+    /// `None` when neither variable is set, cargo then reads config instead
+    fn inherited_from(&self, var: impl Fn(&str) -> Option<String>) -> Option<Vec<String>> {
+        let [encoded, plain] = self.vars();
+        if let Some(flags) = var(encoded) {
+            if flags.is_empty() {
+                return Some(Vec::new());
+            }
+            return Some(flags.split('\x1f').map(str::to_string).collect());
+        }
+        var(plain).map(|flags| flags.split_whitespace().map(str::to_string).collect())
+    }
+
+    /// The `--config` override passing the added flags
+    pub fn config_override(&self) -> ConfigOverride {
+        let key = if self.rustdoc {
+            "rustdocflags"
+        } else {
+            "rustflags"
+        };
+        ConfigOverride::new(["build", key], self.flags.clone())
+    }
+
+    /// Add the flags to `cmd`, see the type level docs for how
+    pub fn apply(&self, cmd: &mut Command) {
+        if self.flags.is_empty() {
+            return;
+        }
+        let inherited = self.inherited_from(|var| {
+            // Variables set or removed on the `Command` win over the process
+            match cmd.get_envs().find(|(key, _)| *key == var) {
+                Some((_, value)) => value.map(|value| value.to_string_lossy().into_owned()),
+                None => env::var(var).ok(),
+            }
+        });
+        match inherited {
+            Some(mut flags) => {
+                flags.extend(self.flags.iter().cloned());
+                cmd.env(self.vars()[0], flags.join("\x1f"));
+            }
+            None => insert_config(cmd, &self.config_override().to_string()),
+        }
+    }
+}
+
+/// Pass `--config <config>` to the cargo subcommand in `cmd`, before any `--`
+///
+/// `Command` can't insert arguments, so with a `--` it is rebuilt from the
+/// program, arguments, environment and working directory, stdio set up on
+/// `cmd` is not carried over.
+fn insert_config(cmd: &mut Command, config: &str) {
+    let args: Vec<OsString> = cmd.get_args().map(OsStr::to_os_string).collect();
+    let Some(end) = args.iter().position(|arg| arg == "--") else {
+        cmd.arg("--config").arg(config);
+        return;
+    };
+    let mut rebuilt = Command::new(cmd.get_program());
+    rebuilt
+        .args(&args[..end])
+        .arg("--config")
+        .arg(config)
+        .args(&args[end..]);
+    for (key, value) in cmd.get_envs() {
+        match value {
+            Some(value) => rebuilt.env(key, value),
+            None => rebuilt.env_remove(key),
+        };
+    }
+    if let Some(dir) = cmd.get_current_dir() {
+        rebuilt.current_dir(dir);
+    }
+    *cmd = rebuilt;
+}
+
+#[cfg(test)]
+mod test {
+    use super::RustFlags;
+    use std::path::Path;
+    use std::process::Command;
+
+    fn encoded(cmd: &Command, var: &str) -> Option<String> {
+        cmd.get_envs()
+            .find(|(key, _)| *key == var)
+            .and_then(|(_, value)| value)
+            .map(|value| value.to_string_lossy().into_owned())
+    }
+
+    #[test]
+    fn merge() {
+        let mut cmd = Command::new("cargo");
+        cmd.env("RUSTFLAGS", "-C  opt-level=1 ");
+        cmd.env_remove("CARGO_ENCODED_RUSTFLAGS");
+        RustFlags::new()
+            .target_cpu("native")
+            .link_arg("-Wl,-rpath,/opt/my libs")
+            .deny("warnings")
+            .apply(&mut cmd);
+        assert_eq!(
+            encoded(&cmd, "CARGO_ENCODED_RUSTFLAGS").unwrap(),
+            "-C\x1fopt-level=1\x1f-C\x1ftarget-cpu=native\x1f-C\x1flink-arg=-Wl,-rpath,/opt/my libs\x1f-D\x1fwarnings"
+        );
+
+        // The encoded variable wins over `RUSTFLAGS`, and an empty one means no flags
+        cmd.env("CARGO_ENCODED_RUSTFLAGS", "");
+        RustFlags::new().cfg("loom").apply(&mut cmd);
+        assert_eq!(
+            encoded(&cmd, "CARGO_ENCODED_RUSTFLAGS").unwrap(),
+            "--cfg\x1floom"
+        );
+        RustFlags::new().allow("unused").apply(&mut cmd);
+        assert_eq!(
+            encoded(&cmd, "CARGO_ENCODED_RUSTFLAGS").unwrap(),
+            "--cfg\x1floom\x1f-A\x1funused"
+        );
+
+        // Without either variable cargo reads config, `CARGO_BUILD_RUSTDOCFLAGS`
+        // included, and merges `--config` into it
+        let mut cmd = Command::new("cargo");
+        cmd.arg("doc").arg("--");
+        cmd.env_remove("CARGO_ENCODED_RUSTDOCFLAGS");
+        cmd.env_remove("RUSTDOCFLAGS");
+        cmd.env("CARGO_BUILD_RUSTDOCFLAGS", "--cfg docsrs");
+        cmd.current_dir("crates/foo");
+        RustFlags::rustdoc().warn("missing_docs").apply(&mut cmd);
+        let args: Vec<_> = cmd.get_args().collect();
+        assert_eq!(
+            args,
+            [
+                "doc",
+                "--config",
+                r#"build.rustdocflags=["-W", "missing_docs"]"#,
+                "--"
+            ]
+        );
+        assert_eq!(encoded(&cmd, "CARGO_ENCODED_RUSTDOCFLAGS"), None);
+        assert_eq!(
+            encoded(&cmd, "CARGO_BUILD_RUSTDOCFLAGS").as_deref(),
+            Some("--cfg docsrs")
+        );
+        assert_eq!(cmd.get_current_dir(), Some(Path::new("crates/foo")));
+    }
+}
@@ -0,0 +1,175 @@
+use std::io;
+use std::ops::{Deref, DerefMut};
+use std::process::Command;
+use std::str::FromStr;
+
+use clap::{ArgAction, Parser};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::common::{self, CommonOptions, GlobalOptions};
+use crate::toml::{parse_string, string_end};
+
+/// Search packages in the registry. Default registry is crates.io
+#[derive(Clone, Debug, Default, Parser)]
+#[command(
+    display_order = 1,
+    after_help = "Run `cargo help search` for more detailed information."
+)]
+#[group(skip)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct Search {
+    #[command(flatten)]
+    #[cfg_attr(feature = "serde", serde(flatten))]
+    pub global_options: GlobalOptions,
+
+    /// Limit the number of results (default: 10, max: 100)
+    #[arg(long, value_name = "LIMIT")]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub limit: Option<u32>,
+
+    /// Registry index URL to search packages in
+    #[arg(long, value_name = "INDEX", conflicts_with = "registry")]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub index: Option<String>,
+
+    /// Registry to search packages in
+    #[arg(long, value_name = "REGISTRY")]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub registry: Option<String>,
+
+    #[arg(value_name = "QUERY", action = ArgAction::Append, num_args = 0..)]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub query: Vec<String>,
+}
+
+impl Search {
+    /// Build a `cargo search` command
+    pub fn command(&self) -> Command {
+        let mut cmd = CommonOptions::cargo_command();
+        self.global_options.apply_top_level(&mut cmd);
+        cmd.arg("search");
+        self.global_options.apply(&mut cmd);
+        if let Some(limit) = self.limit {
+            cmd.arg("--limit").arg(limit.to_string());
+        }
+        if let Some(index) = self.index.as_ref() {
+            cmd.arg("--index").arg(index);
+        }
+        if let Some(registry) = self.registry.as_ref() {
+            cmd.arg("--registry").arg(registry);
+        }
+        cmd.args(&self.query);
+        cmd
+    }
+
+    /// Run `cargo search` and parse the packages it found
+    pub fn results(&self) -> io::Result<SearchResults> {
+        let stdout = common::capture_stdout(&mut self.command())?;
+        stdout.parse()
+    }
+}
+
+impl Deref for Search {
+    type Target = GlobalOptions;
+
+    fn deref(&self) -> &Self::Target {
+        &self.global_options
+    }
+}
+
+impl DerefMut for Search {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.global_options
+    }
+}
+
+/// Parsed `cargo search` output
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct SearchResults {
+    pub packages: Vec<SearchResult>,
+    /// Number of matches left out because of `--limit`
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub remaining: u64,
+}
+
+/// A single `cargo search` match
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct SearchResult {
+    pub name: String,
+    /// Latest version
+    pub version: String,
+    /// Package description, cargo truncates long ones
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub description: Option<String>,
+}
+
+impl FromStr for SearchResults {
+    type Err = io::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut results = SearchResults::default();
+        for line in s.lines() {
+            if let Some(rest) = line.strip_prefix("... and ") {
+                results.remaining = rest
+                    .split_whitespace()
+                    .next()
+                    .and_then(|n| n.parse().ok())
+                    .ok_or_else(|| invalid_data(format!("unexpected line `{}`", line)))?;
+                continue;
+            }
+            let Some((name, rest)) = line.split_once(" = ") else {
+                continue;
+            };
+            let end = string_end(rest)
+                .ok_or_else(|| invalid_data(format!("unexpected line `{}`", line)))?;
+            let description = rest[end..]
+                .trim_start()
+                .strip_prefix('#')
+                .map(|description| description.trim().to_string());
+            results.packages.push(SearchResult {
+                name: name.to_string(),
+                version: parse_string(&rest[..end])?,
+                description,
+            });
+        }
+        Ok(results)
+    }
+}
+
+fn invalid_data(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Search, SearchResults};
+    use clap::CommandFactory;
+
+    #[test]
+    fn verify_cli() {
+        <Search as CommandFactory>::command().debug_assert()
+    }
+
+    #[test]
+    fn parse_results() {
+        let stdout = r#"serde = "1.0.210"              # A generic serialization/deserialization framework
+serde-json-core = "0.6.0"      # serde-json for no_std programs
+serde_nothing = "0.1.1"
+... and 4223 crates more (use --limit N to see more)
+"#;
+        let results: SearchResults = stdout.parse().unwrap();
+        assert_eq!(results.packages.len(), 3);
+        assert_eq!(results.packages[0].name, "serde");
+        assert_eq!(results.packages[0].version, "1.0.210");
+        assert_eq!(
+            results.packages[1].description.as_deref(),
+            Some("serde-json for no_std programs")
+        );
+        assert_eq!(results.packages[2].description, None);
+        assert_eq!(results.remaining, 4223);
+    }
+}
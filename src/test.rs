@@ -1,14 +1,21 @@
+use std::env;
+use std::fs;
+use std::io;
 use std::ops::{Deref, DerefMut};
 use std::path::PathBuf;
 use std::process::Command;
 
-use clap::{ArgAction, Parser};
+use clap::Parser;
 
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
-use crate::common::CommonOptions;
-use crate::heading;
+use crate::common::{
+    self, CommonOptions, CompilationOptions, ManifestOptions, PackageSelection, TargetSelection,
+};
+use crate::json;
+use crate::rustflags::RustFlags;
+use crate::CargoVersion;
 
 /// Execute all unit and integration tests and build examples of a local package
 #[derive(Clone, Debug, Default, Parser)]
@@ -23,129 +30,21 @@ pub struct Test {
     #[cfg_attr(feature = "serde", serde(flatten))]
     pub common: CommonOptions,
 
-    /// Path to Cargo.toml
-    #[arg(long, value_name = "PATH", help_heading = heading::MANIFEST_OPTIONS)]
-    #[cfg_attr(feature = "serde", serde(default))]
-    pub manifest_path: Option<PathBuf>,
-
-    /// Build artifacts in release mode, with optimizations
-    #[arg(short = 'r', long, help_heading = heading::COMPILATION_OPTIONS)]
-    #[cfg_attr(feature = "serde", serde(default))]
-    pub release: bool,
-
-    /// Ignore `rust-version` specification in packages
-    #[arg(long)]
-    #[cfg_attr(feature = "serde", serde(default))]
-    pub ignore_rust_version: bool,
-
-    /// Output build graph in JSON (unstable)
-    #[arg(long, help_heading = heading::COMPILATION_OPTIONS)]
-    #[cfg_attr(feature = "serde", serde(default))]
-    pub unit_graph: bool,
-
-    /// Package to build (see `cargo help pkgid`)
-    #[arg(
-        short = 'p',
-        long = "package",
-        value_name = "SPEC",
-        action = ArgAction::Append,
-        num_args=0..=1,
-        help_heading = heading::PACKAGE_SELECTION,
-    )]
-    #[cfg_attr(feature = "serde", serde(default))]
-    pub packages: Vec<String>,
-
-    /// Test all packages in the workspace
-    #[arg(long, help_heading = heading::PACKAGE_SELECTION)]
-    #[cfg_attr(feature = "serde", serde(default))]
-    pub workspace: bool,
-
-    /// Exclude packages from the build
-    #[arg(
-        long,
-        value_name = "SPEC",
-        action = ArgAction::Append,
-        help_heading = heading::PACKAGE_SELECTION,
-    )]
-    #[cfg_attr(feature = "serde", serde(default))]
-    pub exclude: Vec<String>,
-
-    /// Alias for workspace (deprecated)
-    #[arg(long, help_heading = heading::PACKAGE_SELECTION)]
-    #[cfg_attr(feature = "serde", serde(default))]
-    pub all: bool,
-
-    /// Test only this package's library
-    #[arg(long, help_heading = heading::TARGET_SELECTION)]
-    #[cfg_attr(feature = "serde", serde(default))]
-    pub lib: bool,
-
-    /// Test only the specified binary
-    #[arg(
-        long,
-        value_name = "NAME",
-        action = ArgAction::Append,
-        num_args=0..=1,
-        help_heading = heading::TARGET_SELECTION,
-    )]
-    #[cfg_attr(feature = "serde", serde(default))]
-    pub bin: Vec<String>,
-
-    /// Test all binaries
-    #[arg(long, help_heading = heading::TARGET_SELECTION)]
-    #[cfg_attr(feature = "serde", serde(default))]
-    pub bins: bool,
-
-    /// Test only the specified example
-    #[arg(
-        long,
-        value_name = "NAME",
-        action = ArgAction::Append,
-        num_args=0..=1,
-        help_heading = heading::TARGET_SELECTION,
-    )]
-    #[cfg_attr(feature = "serde", serde(default))]
-    pub example: Vec<String>,
-
-    /// Test all examples
-    #[arg(long, help_heading = heading::TARGET_SELECTION)]
-    #[cfg_attr(feature = "serde", serde(default))]
-    pub examples: bool,
-
-    /// Test only the specified test target
-    #[arg(
-        long,
-        value_name = "NAME",
-        action = ArgAction::Append,
-        help_heading = heading::TARGET_SELECTION,
-    )]
-    #[cfg_attr(feature = "serde", serde(default))]
-    pub test: Vec<String>,
+    #[command(flatten)]
+    #[cfg_attr(feature = "serde", serde(flatten))]
+    pub compilation_options: CompilationOptions,
 
-    /// Test all tests
-    #[arg(long, help_heading = heading::TARGET_SELECTION)]
-    #[cfg_attr(feature = "serde", serde(default))]
-    pub tests: bool,
-
-    /// Test only the specified bench target
-    #[arg(
-        long,
-        value_name = "NAME",
-        action = ArgAction::Append,
-        help_heading = heading::TARGET_SELECTION,
-    )]
-    #[cfg_attr(feature = "serde", serde(default))]
-    pub bench: Vec<String>,
+    #[command(flatten)]
+    #[cfg_attr(feature = "serde", serde(flatten))]
+    pub manifest_options: ManifestOptions,
 
-    /// Test all benches
-    #[arg(long, help_heading = heading::TARGET_SELECTION)]
-    #[cfg_attr(feature = "serde", serde(default))]
-    pub benches: bool,
+    #[command(flatten)]
+    #[cfg_attr(feature = "serde", serde(flatten))]
+    pub package_selection: PackageSelection,
 
-    /// Test all targets
-    #[arg(long, help_heading = heading::TARGET_SELECTION)]
-    #[cfg_attr(feature = "serde", serde(default))]
-    pub all_targets: bool,
+    #[command(flatten)]
+    #[cfg_attr(feature = "serde", serde(flatten))]
+    pub target_selection: TargetSelection,
 
     /// Test only this library's documentation
     #[arg(long)]
@@ -182,64 +81,16 @@ impl Test {
     /// Build a `cargo test` command
     pub fn command(&self) -> Command {
         let mut cmd = CommonOptions::cargo_command();
+        self.common.global_options.apply_top_level(&mut cmd);
         cmd.arg("test");
 
-        self.common.apply(&mut cmd);
+        self.common
+            .apply_with_unstable(&mut cmd, &self.unstable_options());
 
-        if let Some(path) = self.manifest_path.as_ref() {
-            cmd.arg("--manifest-path").arg(path);
-        }
-        if self.release {
-            cmd.arg("--release");
-        }
-        if self.ignore_rust_version {
-            cmd.arg("--ignore-rust-version");
-        }
-        if self.unit_graph {
-            cmd.arg("--unit-graph");
-        }
-        for pkg in &self.packages {
-            cmd.arg("--package").arg(pkg);
-        }
-        if self.workspace {
-            cmd.arg("--workspace");
-        }
-        for item in &self.exclude {
-            cmd.arg("--exclude").arg(item);
-        }
-        if self.all {
-            cmd.arg("--all");
-        }
-        if self.lib {
-            cmd.arg("--lib");
-        }
-        for bin in &self.bin {
-            cmd.arg("--bin").arg(bin);
-        }
-        if self.bins {
-            cmd.arg("--bins");
-        }
-        for example in &self.example {
-            cmd.arg("--example").arg(example);
-        }
-        if self.examples {
-            cmd.arg("--examples");
-        }
-        for test in &self.test {
-            cmd.arg("--test").arg(test);
-        }
-        if self.tests {
-            cmd.arg("--tests");
-        }
-        for bench in &self.bench {
-            cmd.arg("--bench").arg(bench);
-        }
-        if self.benches {
-            cmd.arg("--benches");
-        }
-        if self.all_targets {
-            cmd.arg("--all-targets");
-        }
+        self.compilation_options.apply(&mut cmd);
+        self.manifest_options.apply(&mut cmd);
+        self.package_selection.apply(&mut cmd);
+        self.target_selection.apply(&mut cmd);
         if self.doc {
             cmd.arg("--doc");
         }
@@ -260,6 +111,148 @@ impl Test {
 
         cmd
     }
+
+    /// Run these tests with source-based code coverage
+    ///
+    /// The instrumented build goes to `coverage` inside the target directory,
+    /// so it doesn't invalidate regular builds.
+    pub fn coverage(&self) -> TestCoverage {
+        let target_dir = common::target_dir(
+            self.common.target_dir.as_deref(),
+            self.manifest_options.manifest_path.as_deref(),
+        )
+        .join("coverage");
+        // Test binaries run in their package directory, so LLVM_PROFILE_FILE
+        // needs an absolute path
+        let target_dir = env::current_dir()
+            .map(|cwd| cwd.join(&target_dir))
+            .unwrap_or(target_dir);
+        let mut test = self.clone();
+        test.common.target_dir = Some(target_dir.clone());
+        test.common.provenance.remove("target_dir");
+        TestCoverage {
+            test,
+            profile_dir: target_dir.join("profraw"),
+        }
+    }
+
+    /// Build a `cargo test` command that `version` understands
+    ///
+    /// `-Z unstable-options` is added when an option is unstable on `version`
+    /// and options `version` doesn't have (anymore) are an error.
+    pub fn command_for(&self, version: &CargoVersion) -> io::Result<Command> {
+        if self.keep_going && version.is_at_least(1, 73) {
+            return Err(common::unsupported(
+                "--keep-going",
+                "was removed from `cargo test` in cargo 1.73",
+                version,
+            ));
+        }
+        let mut test = self.clone();
+        test.common.adapt_to_version(version)?;
+        Ok(test.command())
+    }
+
+    /// Unstable options in use, cargo only takes them with `-Z unstable-options`
+    pub fn unstable_options(&self) -> Vec<&'static str> {
+        let mut options = self.common.unstable_options();
+        options.extend(self.compilation_options.unstable_options());
+        options
+    }
+
+    /// Whether the options only work on a nightly cargo
+    pub fn requires_nightly(&self) -> bool {
+        self.common.requires_nightly_for(&self.unstable_options())
+    }
+}
+
+/// A `cargo test` run instrumented for source-based code coverage
+#[derive(Clone, Debug)]
+pub struct TestCoverage {
+    /// The tests to run, with `--target-dir` pointing at the isolated directory
+    pub test: Test,
+    /// Directory the `.profraw` files are written to
+    pub profile_dir: PathBuf,
+}
+
+impl TestCoverage {
+    /// Build the `cargo test` command with `-C instrument-coverage` and
+    /// `LLVM_PROFILE_FILE` set
+    ///
+    /// `-C instrument-coverage` is added to the rustflags cargo would use
+    /// anyway, from the environment or from config files. Config that sets
+    /// `target.<triple>.rustflags` needs it added there, see [`RustFlags`].
+    pub fn command(&self) -> Command {
+        let mut cmd = self.test.command();
+        self.apply_env(&mut cmd);
+        cmd
+    }
+
+    fn apply_env(&self, cmd: &mut Command) {
+        RustFlags::new()
+            .args(["-C", "instrument-coverage"])
+            .apply(cmd);
+        cmd.env(
+            "LLVM_PROFILE_FILE",
+            self.profile_dir.join("cargo-test-%p-%m.profraw"),
+        );
+    }
+
+    /// `.profraw` files written by previous runs of `command`
+    pub fn profraw_files(&self) -> io::Result<Vec<PathBuf>> {
+        let entries = match fs::read_dir(&self.profile_dir) {
+            Ok(entries) => entries,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(err) => return Err(err),
+        };
+        let mut files = Vec::new();
+        for entry in entries {
+            let path = entry?.path();
+            if path.extension().is_some_and(|ext| ext == "profraw") {
+                files.push(path);
+            }
+        }
+        files.sort();
+        Ok(files)
+    }
+
+    /// Remove `.profraw` files left by previous runs
+    pub fn remove_profraw_files(&self) -> io::Result<()> {
+        for file in self.profraw_files()? {
+            fs::remove_file(file)?;
+        }
+        Ok(())
+    }
+
+    /// Instrumented executables to pass to `llvm-cov` as objects
+    ///
+    /// Runs the same build with `--no-run --message-format json`, which is
+    /// a no-op after `command` and lists exactly the current binaries.
+    pub fn binaries(&self) -> io::Result<Vec<PathBuf>> {
+        let mut test = self.test.clone();
+        test.no_run = true;
+        test.common.message_format = vec!["json".to_string()];
+        test.test_name = None;
+        test.args.clear();
+        let mut cmd = test.command();
+        self.apply_env(&mut cmd);
+        let stdout = common::capture_stdout(&mut cmd)?;
+        let mut binaries = Vec::new();
+        for line in stdout.lines() {
+            // Output of build scripts is passed through, skip anything that isn't a message
+            if !line.starts_with('{') {
+                continue;
+            }
+            let message = json::parse(line)?;
+            if message.get("reason").and_then(json::Value::as_str) != Some("compiler-artifact") {
+                continue;
+            }
+            if let Some(executable) = message.get("executable").and_then(json::Value::as_str) {
+                binaries.push(PathBuf::from(executable));
+            }
+        }
+        Ok(binaries)
+    }
 }
 
 impl Deref for Test {
@@ -279,10 +272,73 @@ impl DerefMut for Test {
 #[cfg(test)]
 mod tests {
     use super::Test;
-    use clap::CommandFactory;
+    use crate::{CargoVersion, OptionSource};
+    use clap::{CommandFactory, Parser};
+    use std::env;
+    use std::io;
 
     #[test]
     fn verify_cli() {
         <Test as CommandFactory>::command().debug_assert()
     }
+
+    #[test]
+    fn command_for_version() {
+        let version =
+            |release: &str| -> CargoVersion { format!("release: {}\n", release).parse().unwrap() };
+        let test = Test::parse_from(["test", "--keep-going"]);
+        let cmd = test.command_for(&version("1.72.0-nightly")).unwrap();
+        assert!(cmd.get_args().any(|a| a == "unstable-options"));
+        let err = test.command_for(&version("1.73.0")).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::Unsupported);
+    }
+
+    #[test]
+    fn coverage() {
+        let test = Test::parse_from([
+            "test",
+            "--target-dir",
+            "out",
+            "-p",
+            "foo",
+            "--",
+            "--nocapture",
+        ]);
+        let coverage = test.coverage();
+        let target_dir = env::current_dir().unwrap().join("out").join("coverage");
+        assert_eq!(coverage.profile_dir, target_dir.join("profraw"));
+
+        let mut cmd = coverage.test.command();
+        cmd.env_remove("CARGO_ENCODED_RUSTFLAGS")
+            .env_remove("RUSTFLAGS");
+        coverage.apply_env(&mut cmd);
+        let args: Vec<_> = cmd.get_args().map(|a| a.to_owned()).collect();
+        assert!(args
+            .windows(2)
+            .any(|w| w[0] == "--target-dir" && w[1] == target_dir.as_os_str()));
+        assert!(args.ends_with(&[
+            "--config".into(),
+            r#"build.rustflags=["-C", "instrument-coverage"]"#.into(),
+            "--".into(),
+            "--nocapture".into()
+        ]));
+        let envs: Vec<_> = cmd.get_envs().collect();
+        assert!(envs.iter().any(|(key, _)| *key == "LLVM_PROFILE_FILE"));
+        assert!(coverage.profraw_files().unwrap().is_empty());
+
+        // The isolated directory is passed on even if the original one was
+        // read from `CARGO_TARGET_DIR`
+        let mut test = Test::parse_from(["test", "--target-dir", "out"]);
+        test.provenance
+            .insert("target_dir", OptionSource::Environment);
+        let args: Vec<_> = test
+            .coverage()
+            .command()
+            .get_args()
+            .map(|a| a.to_owned())
+            .collect();
+        assert!(args
+            .windows(2)
+            .any(|w| w[0] == "--target-dir" && w[1] == target_dir.as_os_str()));
+    }
 }
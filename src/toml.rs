@@ -0,0 +1,180 @@
+//! Just enough TOML to read and write the small snippets cargo deals in
+
+use std::io;
+
+fn invalid_data(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+pub(crate) fn is_bare_key(key: &str) -> bool {
+    !key.is_empty()
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+pub(crate) fn parse_key(key: &str) -> io::Result<String> {
+    if key.starts_with('"') {
+        parse_string(key)
+    } else if is_bare_key(key) {
+        Ok(key.to_string())
+    } else {
+        Err(invalid_data(format!("invalid key `{}`", key)))
+    }
+}
+
+pub(crate) fn render_key(key: &str) -> String {
+    if is_bare_key(key) {
+        key.to_string()
+    } else {
+        render_string(key)
+    }
+}
+
+/// Split `a."b.c".d = rest` into its key parts and whatever follows `separator`
+pub(crate) fn parse_dotted_key(s: &str, separator: char) -> io::Result<(Vec<String>, &str)> {
+    let invalid = || invalid_data(format!("invalid key in `{}`", s));
+    let mut parts = Vec::new();
+    let mut rest = s.trim_start();
+    loop {
+        let end = if rest.starts_with('"') {
+            string_end(rest)
+        } else if rest.starts_with('\'') {
+            literal_string_end(rest)
+        } else {
+            let end = rest
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '-' || c == '_'))
+                .unwrap_or(rest.len());
+            Some(end).filter(|&end| end > 0)
+        }
+        .ok_or_else(invalid)?;
+        let part = &rest[..end];
+        parts.push(match part.as_bytes()[0] {
+            b'"' => parse_string(part)?,
+            b'\'' => parse_literal_string(part)?,
+            _ => part.to_string(),
+        });
+        rest = rest[end..].trim_start();
+        if let Some(next) = rest.strip_prefix('.') {
+            rest = next.trim_start();
+        } else if let Some(value) = rest.strip_prefix(separator) {
+            return Ok((parts, value));
+        } else {
+            return Err(invalid());
+        }
+    }
+}
+
+/// Render key parts as a dotted key, quoting the parts that need it
+pub(crate) fn render_dotted_key<S: AsRef<str>>(parts: &[S]) -> String {
+    parts
+        .iter()
+        .map(|part| render_key(part.as_ref()))
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+/// Parse a TOML basic string, i.e. `"..."` with backslash escapes
+pub(crate) fn parse_string(s: &str) -> io::Result<String> {
+    let inner = s
+        .strip_prefix('"')
+        .and_then(|s| s.strip_suffix('"'))
+        .filter(|_| s.len() >= 2)
+        .ok_or_else(|| invalid_data(format!("expected a quoted string, got `{}`", s)))?;
+    let mut out = String::with_capacity(inner.len());
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('"') => out.push('"'),
+            Some('\\') => out.push('\\'),
+            Some('n') => out.push('\n'),
+            Some('t') => out.push('\t'),
+            Some('r') => out.push('\r'),
+            Some(esc @ ('u' | 'U')) => {
+                let len = if esc == 'u' { 4 } else { 8 };
+                let hex: String = chars.by_ref().take(len).collect();
+                let c = u32::from_str_radix(&hex, 16)
+                    .ok()
+                    .and_then(char::from_u32)
+                    .ok_or_else(|| invalid_data(format!("invalid unicode escape in `{}`", s)))?;
+                out.push(c);
+            }
+            _ => return Err(invalid_data(format!("invalid escape in `{}`", s))),
+        }
+    }
+    Ok(out)
+}
+
+/// Render a TOML basic string
+pub(crate) fn render_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            '\r' => out.push_str("\\r"),
+            c if c.is_control() => out.push_str(&format!("\\u{:04X}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// Parse a single-line TOML array of strings, e.g. `["a", 'b']`
+pub(crate) fn parse_string_array(s: &str) -> io::Result<Vec<String>> {
+    let inner = s
+        .trim()
+        .strip_prefix('[')
+        .and_then(|s| s.strip_suffix(']'))
+        .ok_or_else(|| invalid_data(format!("expected an array, got `{}`", s)))?;
+    let mut items = Vec::new();
+    let mut rest = inner.trim_start();
+    while !rest.is_empty() {
+        let (end, parse): (_, fn(&str) -> io::Result<String>) = if rest.starts_with('\'') {
+            (literal_string_end(rest), parse_literal_string)
+        } else {
+            (string_end(rest), parse_string)
+        };
+        let end = end.ok_or_else(|| invalid_data(format!("unterminated string in `{}`", s)))?;
+        items.push(parse(&rest[..end])?);
+        rest = rest[end..].trim_start();
+        rest = rest.strip_prefix(',').unwrap_or(rest).trim_start();
+    }
+    Ok(items)
+}
+
+/// Parse a TOML literal string, i.e. `'...'` without escapes
+pub(crate) fn parse_literal_string(s: &str) -> io::Result<String> {
+    s.strip_prefix('\'')
+        .and_then(|s| s.strip_suffix('\''))
+        .filter(|inner| !inner.contains('\''))
+        .map(str::to_string)
+        .ok_or_else(|| invalid_data(format!("expected a literal string, got `{}`", s)))
+}
+
+/// Byte offset just past the literal string `s` starts with
+pub(crate) fn literal_string_end(s: &str) -> Option<usize> {
+    s.get(1..)?.find('\'').map(|i| i + 2)
+}
+
+/// Byte offset just past the basic string `s` starts with
+pub(crate) fn string_end(s: &str) -> Option<usize> {
+    let mut escaped = false;
+    for (i, c) in s.char_indices().skip(1) {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '"' => return Some(i + 1),
+            _ => {}
+        }
+    }
+    None
+}
@@ -0,0 +1,102 @@
+use std::ops::{Deref, DerefMut};
+use std::path::PathBuf;
+use std::process::Command;
+
+use clap::{ArgAction, Parser};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::common::{CommonOptions, GlobalOptions};
+use crate::heading;
+
+/// Remove a Rust binary
+#[derive(Clone, Debug, Default, Parser)]
+#[command(
+    display_order = 1,
+    after_help = "Run `cargo help uninstall` for more detailed information."
+)]
+#[group(skip)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct Uninstall {
+    #[command(flatten)]
+    #[cfg_attr(feature = "serde", serde(flatten))]
+    pub global_options: GlobalOptions,
+
+    /// Package to uninstall
+    #[arg(
+        short = 'p',
+        long = "package",
+        value_name = "SPEC",
+        action = ArgAction::Append,
+        num_args=0..=1,
+        help_heading = heading::PACKAGE_SELECTION,
+    )]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub packages: Vec<String>,
+
+    /// Only uninstall the binary NAME
+    #[arg(
+        long,
+        value_name = "NAME",
+        action = ArgAction::Append,
+        help_heading = heading::TARGET_SELECTION,
+    )]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub bin: Vec<String>,
+
+    /// Directory to uninstall packages from
+    #[arg(long, value_name = "DIR")]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub root: Option<PathBuf>,
+
+    #[arg(value_name = "SPEC", action = ArgAction::Append, num_args = 0..)]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub specs: Vec<String>,
+}
+
+impl Uninstall {
+    /// Build a `cargo uninstall` command
+    pub fn command(&self) -> Command {
+        let mut cmd = CommonOptions::cargo_command();
+        self.global_options.apply_top_level(&mut cmd);
+        cmd.arg("uninstall");
+        self.global_options.apply(&mut cmd);
+        for pkg in &self.packages {
+            cmd.arg("--package").arg(pkg);
+        }
+        for bin in &self.bin {
+            cmd.arg("--bin").arg(bin);
+        }
+        if let Some(root) = self.root.as_ref() {
+            cmd.arg("--root").arg(root);
+        }
+        cmd.args(&self.specs);
+        cmd
+    }
+}
+
+impl Deref for Uninstall {
+    type Target = GlobalOptions;
+
+    fn deref(&self) -> &Self::Target {
+        &self.global_options
+    }
+}
+
+impl DerefMut for Uninstall {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.global_options
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Uninstall;
+    use clap::CommandFactory;
+
+    #[test]
+    fn verify_cli() {
+        <Uninstall as CommandFactory>::command().debug_assert()
+    }
+}
@@ -0,0 +1,277 @@
+use std::fmt;
+use std::io;
+use std::ops::{Deref, DerefMut};
+use std::path::PathBuf;
+use std::process::Command;
+use std::str::FromStr;
+
+use clap::{ArgAction, Parser};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::common::{self, CommonOptions, GlobalOptions};
+use crate::heading;
+use crate::toml::{parse_key, parse_string, render_key, render_string};
+
+/// Vendor all dependencies for a project locally
+#[derive(Clone, Debug, Default, Parser)]
+#[command(
+    display_order = 1,
+    after_help = "Run `cargo help vendor` for more detailed information."
+)]
+#[group(skip)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct Vendor {
+    #[command(flatten)]
+    #[cfg_attr(feature = "serde", serde(flatten))]
+    pub global_options: GlobalOptions,
+
+    /// Don't delete older crates in the vendor directory
+    #[arg(long)]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub no_delete: bool,
+
+    /// Additional `Cargo.toml` to sync and vendor
+    #[arg(short = 's', long, value_name = "TOML", action = ArgAction::Append)]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub sync: Vec<PathBuf>,
+
+    /// Respect `[source]` config in `.cargo/config`
+    #[arg(long)]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub respect_source_config: bool,
+
+    /// Always include version in subdir name
+    #[arg(long)]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub versioned_dirs: bool,
+
+    /// Path to Cargo.toml
+    #[arg(long, value_name = "PATH", help_heading = heading::MANIFEST_OPTIONS)]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub manifest_path: Option<PathBuf>,
+
+    /// Where to vendor crates (`vendor` by default)
+    #[arg(value_name = "path")]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub path: Option<PathBuf>,
+}
+
+impl Vendor {
+    /// Build a `cargo vendor` command
+    pub fn command(&self) -> Command {
+        let mut cmd = CommonOptions::cargo_command();
+        self.global_options.apply_top_level(&mut cmd);
+        cmd.arg("vendor");
+        self.global_options.apply(&mut cmd);
+        if self.no_delete {
+            cmd.arg("--no-delete");
+        }
+        for sync in &self.sync {
+            cmd.arg("--sync").arg(sync);
+        }
+        if self.respect_source_config {
+            cmd.arg("--respect-source-config");
+        }
+        if self.versioned_dirs {
+            cmd.arg("--versioned-dirs");
+        }
+        if let Some(path) = self.manifest_path.as_ref() {
+            cmd.arg("--manifest-path").arg(path);
+        }
+        if let Some(path) = self.path.as_ref() {
+            cmd.arg(path);
+        }
+        cmd
+    }
+
+    /// Run `cargo vendor` and return the source replacement configuration
+    /// it prints, ready to be written to `.cargo/config.toml`
+    pub fn source_config(&self) -> io::Result<VendorConfig> {
+        let stdout = common::capture_stdout(&mut self.command())?;
+        stdout.parse()
+    }
+}
+
+impl Deref for Vendor {
+    type Target = GlobalOptions;
+
+    fn deref(&self) -> &Self::Target {
+        &self.global_options
+    }
+}
+
+impl DerefMut for Vendor {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.global_options
+    }
+}
+
+/// Source replacement configuration printed by `cargo vendor`
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct VendorConfig {
+    /// `[source.<name>]` tables in the order cargo printed them
+    pub sources: Vec<VendorSource>,
+}
+
+/// A single `[source.<name>]` table
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct VendorSource {
+    /// Name of the source, e.g. `crates-io` or `vendored-sources`
+    pub name: String,
+    /// Name of the source this one is replaced with
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub replace_with: Option<String>,
+    /// Vendor directory backing a directory source
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub directory: Option<PathBuf>,
+    /// Index URL of a replaced registry source
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub registry: Option<String>,
+    /// Repository URL of a replaced git source
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub git: Option<String>,
+    /// Branch of a replaced git source
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub branch: Option<String>,
+    /// Tag of a replaced git source
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub tag: Option<String>,
+    /// Revision of a replaced git source
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub rev: Option<String>,
+}
+
+impl VendorConfig {
+    /// Find a source table by name
+    pub fn source(&self, name: &str) -> Option<&VendorSource> {
+        self.sources.iter().find(|source| source.name == name)
+    }
+}
+
+impl FromStr for VendorConfig {
+    type Err = io::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut sources: Vec<VendorSource> = Vec::new();
+        for line in s.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if let Some(header) = line.strip_prefix('[') {
+                let name = header
+                    .strip_suffix(']')
+                    .and_then(|header| header.trim().strip_prefix("source."))
+                    .ok_or_else(|| invalid_data(format!("unexpected table `{}`", line)))?;
+                sources.push(VendorSource {
+                    name: parse_key(name)?,
+                    ..Default::default()
+                });
+                continue;
+            }
+            let source = sources
+                .last_mut()
+                .ok_or_else(|| invalid_data(format!("`{}` outside of a source table", line)))?;
+            let (key, value) = line
+                .split_once('=')
+                .ok_or_else(|| invalid_data(format!("expected `key = value`, got `{}`", line)))?;
+            let value = parse_string(value.trim())?;
+            match key.trim() {
+                "replace-with" => source.replace_with = Some(value),
+                "directory" => source.directory = Some(PathBuf::from(value)),
+                "registry" => source.registry = Some(value),
+                "git" => source.git = Some(value),
+                "branch" => source.branch = Some(value),
+                "tag" => source.tag = Some(value),
+                "rev" => source.rev = Some(value),
+                key => return Err(invalid_data(format!("unknown source key `{}`", key))),
+            }
+        }
+        Ok(Self { sources })
+    }
+}
+
+impl fmt::Display for VendorConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, source) in self.sources.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{}", source)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for VendorSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "[source.{}]", render_key(&self.name))?;
+        let directory = self.directory.as_ref().map(|dir| dir.to_string_lossy());
+        let entries = [
+            ("registry", self.registry.as_deref()),
+            ("git", self.git.as_deref()),
+            ("branch", self.branch.as_deref()),
+            ("tag", self.tag.as_deref()),
+            ("rev", self.rev.as_deref()),
+            ("replace-with", self.replace_with.as_deref()),
+            ("directory", directory.as_deref()),
+        ];
+        for (key, value) in entries {
+            if let Some(value) = value {
+                writeln!(f, "{} = {}", key, render_string(value))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn invalid_data(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Vendor, VendorConfig};
+    use clap::CommandFactory;
+
+    #[test]
+    fn verify_cli() {
+        <Vendor as CommandFactory>::command().debug_assert()
+    }
+
+    #[test]
+    fn parse_source_config() {
+        let stdout = r#"
+[source.crates-io]
+replace-with = "vendored-sources"
+
+[source."git+https://github.com/messense/cargo-options?branch=main"]
+git = "https://github.com/messense/cargo-options"
+branch = "main"
+replace-with = "vendored-sources"
+
+[source.vendored-sources]
+directory = "third_party/vendor"
+"#;
+        let config: VendorConfig = stdout.parse().unwrap();
+        assert_eq!(config.sources.len(), 3);
+        let git = &config.sources[1];
+        assert_eq!(
+            git.name,
+            "git+https://github.com/messense/cargo-options?branch=main"
+        );
+        assert_eq!(git.branch.as_deref(), Some("main"));
+        assert_eq!(
+            config.source("vendored-sources").unwrap().directory,
+            Some("third_party/vendor".into())
+        );
+
+        let rendered = config.to_string();
+        assert_eq!(rendered.trim(), stdout.trim());
+        assert_eq!(rendered.parse::<VendorConfig>().unwrap(), config);
+    }
+}
@@ -0,0 +1,168 @@
+use std::io;
+use std::ops::{Deref, DerefMut};
+use std::process::Command;
+
+use clap::Parser;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::common::{self, CommonOptions, GlobalOptions};
+
+/// Remove a pushed crate from the index
+#[derive(Clone, Debug, Default, Parser)]
+#[command(
+    display_order = 1,
+    after_help = "Run `cargo help yank` for more detailed information."
+)]
+#[group(skip)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct Yank {
+    #[command(flatten)]
+    #[cfg_attr(feature = "serde", serde(flatten))]
+    pub global_options: GlobalOptions,
+
+    /// The version to yank or un-yank
+    #[arg(long, value_name = "VERSION")]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub version: Option<String>,
+
+    /// Undo a yank, putting a version back into the index
+    #[arg(long)]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub undo: bool,
+
+    /// Registry index URL to yank from
+    #[arg(long, value_name = "INDEX", conflicts_with = "registry")]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub index: Option<String>,
+
+    /// Registry to yank from
+    #[arg(long, value_name = "REGISTRY")]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub registry: Option<String>,
+
+    /// API token to use when authenticating
+    ///
+    /// Never passed on the command line, see `run`.
+    #[arg(skip)]
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub token: Option<String>,
+
+    #[arg(value_name = "CRATE")]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub crate_name: Option<String>,
+}
+
+impl Yank {
+    /// Build a `cargo yank` command
+    ///
+    /// `token` is left out, the command is safe to log.
+    pub fn command(&self) -> Command {
+        let mut cmd = CommonOptions::cargo_command();
+        self.global_options.apply_top_level(&mut cmd);
+        cmd.arg("yank");
+        self.global_options.apply(&mut cmd);
+        if let Some(version) = self.version.as_ref() {
+            cmd.arg("--version").arg(version);
+        }
+        if self.undo {
+            cmd.arg("--undo");
+        }
+        common::apply_registry(
+            &mut cmd,
+            self.registry.as_deref(),
+            self.index.as_deref(),
+            self.token.is_some(),
+        );
+        if let Some(crate_name) = self.crate_name.as_ref() {
+            cmd.arg(crate_name);
+        }
+        cmd
+    }
+
+    /// Run `cargo yank`, passing `token` through the environment
+    ///
+    /// Unlike the `Debug` output of a `Command` with the token set, errors
+    /// returned here never include it.
+    pub fn run(&self) -> io::Result<()> {
+        common::run_with_secret(&mut self.command_with_token(), None)
+    }
+
+    /// `command` with `token` exported to the registry's token variable
+    fn command_with_token(&self) -> Command {
+        let mut cmd = self.command();
+        if let Some(token) = self.token.as_ref() {
+            cmd.env(
+                common::registry_token_env(self.registry.as_deref(), self.index.as_deref()),
+                token,
+            );
+        }
+        cmd
+    }
+}
+
+impl Deref for Yank {
+    type Target = GlobalOptions;
+
+    fn deref(&self) -> &Self::Target {
+        &self.global_options
+    }
+}
+
+impl DerefMut for Yank {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.global_options
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Yank;
+    use clap::{CommandFactory, Parser};
+
+    #[test]
+    fn verify_cli() {
+        <Yank as CommandFactory>::command().debug_assert()
+    }
+
+    #[test]
+    fn index_with_token() {
+        let mut yank = Yank::parse_from([
+            "yank",
+            "--version",
+            "0.1.0",
+            "--index",
+            "sparse+https://registry.example.com/index/",
+            "foo",
+        ]);
+        let args: Vec<_> = yank.command().get_args().map(|a| a.to_owned()).collect();
+        assert!(args.contains(&"--index".into()));
+
+        // cargo only accepts `--token` for bare index URLs, so name the index instead
+        yank.token = Some("s3cr3t".to_string());
+        let cmd = yank.command();
+        assert!(!format!("{:?}", cmd).contains("s3cr3t"));
+        let args: Vec<_> = cmd.get_args().map(|a| a.to_owned()).collect();
+        assert_eq!(
+            args,
+            [
+                "yank",
+                "--version",
+                "0.1.0",
+                "--registry",
+                "cargo-options-index",
+                "--config",
+                "registries.cargo-options-index.index=\"sparse+https://registry.example.com/index/\"",
+                "foo"
+            ]
+        );
+        let cmd = yank.command_with_token();
+        let token = cmd
+            .get_envs()
+            .find(|(key, _)| *key == "CARGO_REGISTRIES_CARGO_OPTIONS_INDEX_TOKEN")
+            .and_then(|(_, value)| value);
+        assert_eq!(token, Some("s3cr3t".as_ref()));
+        assert_eq!(cmd.get_args().count(), args.len());
+    }
+}
@@ -0,0 +1,155 @@
+//! `cargo search` and `cargo info` against local stand-ins, nothing here talks to crates.io
+
+use std::fs;
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpListener;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::thread;
+
+use cargo_options::{Info, PackageInfo, Search, SearchResults};
+use clap::Parser;
+
+fn scratch_dir(name: &str) -> PathBuf {
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join(name);
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn run(mut cmd: Command, dir: &Path) -> String {
+    let output = cmd
+        .current_dir(dir)
+        .env("CARGO_HOME", dir.join("cargo-home"))
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{:?} failed: {}",
+        cmd,
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout).unwrap()
+}
+
+/// Serve a sparse index `config.json` and the search API on a local port
+fn serve_registry() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let mut reader = BufReader::new(&stream);
+            let mut request = String::new();
+            reader.read_line(&mut request).unwrap();
+            let mut header = String::new();
+            while reader.read_line(&mut header).unwrap() > 2 {
+                header.clear();
+            }
+            let path = request.split_whitespace().nth(1).unwrap_or_default();
+            let (status, body) = if path == "/index/config.json" {
+                (
+                    "200 OK",
+                    format!(r#"{{"dl":"http://{addr}/dl","api":"http://{addr}"}}"#),
+                )
+            } else if path.starts_with("/api/v1/crates?q=foo&per_page=2") {
+                (
+                    "200 OK",
+                    r#"{"crates":[
+                        {"name":"foo","max_version":"0.1.0","description":"A foo crate"},
+                        {"name":"foo-bar","max_version":"2.0.0-rc.1","description":null}
+                    ],"meta":{"total":5}}"#
+                        .to_string(),
+                )
+            } else {
+                ("404 Not Found", String::new())
+            };
+            write!(
+                stream,
+                "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                status,
+                body.len(),
+                body
+            )
+            .unwrap();
+        }
+    });
+    format!("sparse+http://{}/index/", addr)
+}
+
+#[test]
+fn search_http_registry() {
+    let dir = scratch_dir("search");
+    let index = serve_registry();
+    let search = Search::parse_from(["search", "--index", &index, "--limit", "2", "foo"]);
+    let results: SearchResults = run(search.command(), &dir).parse().unwrap();
+    assert_eq!(results.packages.len(), 2);
+    assert_eq!(results.packages[0].name, "foo");
+    assert_eq!(
+        results.packages[0].description.as_deref(),
+        Some("A foo crate")
+    );
+    assert_eq!(results.packages[1].version, "2.0.0-rc.1");
+    assert_eq!(results.packages[1].description, None);
+    assert_eq!(results.remaining, 3);
+}
+
+#[test]
+fn info_directory_registry() {
+    let dir = scratch_dir("info");
+    let package = dir.join("vendor").join("foo");
+    fs::create_dir_all(package.join("src")).unwrap();
+    fs::write(package.join("src").join("lib.rs"), "").unwrap();
+    fs::write(
+        package.join(".cargo-checksum.json"),
+        r#"{"files":{},"package":null}"#,
+    )
+    .unwrap();
+    fs::write(
+        package.join("Cargo.toml"),
+        r#"[package]
+name = "foo"
+version = "0.1.0"
+edition = "2021"
+description = "A foo crate"
+license = "MIT"
+keywords = ["foo"]
+
+[features]
+default = ["std"]
+std = []
+extra = []
+"#,
+    )
+    .unwrap();
+
+    // Replace crates.io with a directory source, `cargo info` then reads the
+    // manifest straight from disk
+    let vendor = dir.join("vendor").display().to_string();
+    let info = Info::parse_from([
+        "info",
+        "--registry",
+        "crates-io",
+        "--config",
+        "source.crates-io.replace-with=\"local\"",
+        "--config",
+        &format!("source.local.directory={:?}", vendor),
+        "foo",
+    ]);
+    let info: PackageInfo = run(info.command(), &dir).parse().unwrap();
+    assert_eq!(info.name, "foo");
+    assert_eq!(info.version, "0.1.0");
+    assert_eq!(info.description.as_deref(), Some("A foo crate"));
+    assert_eq!(info.keywords, ["foo"]);
+    assert_eq!(info.license.as_deref(), Some("MIT"));
+    let features: Vec<_> = info
+        .features
+        .iter()
+        .map(|f| (f.name.as_str(), f.default))
+        .collect();
+    assert_eq!(
+        features,
+        [("default", true), ("std", false), ("extra", false)]
+    );
+    assert_eq!(info.features[0].enables, ["std"]);
+}
@@ -0,0 +1,67 @@
+//! `RustFlags` against a real cargo, together with rustflags from config files
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use cargo_options::{Check, RustFlags};
+use clap::Parser;
+
+/// A package that only compiles with `--cfg from_config` and `--cfg from_api`,
+/// the former set in `.cargo/config.toml`
+fn scratch_package(name: &str) -> PathBuf {
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join(name);
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(dir.join("src")).unwrap();
+    fs::create_dir_all(dir.join(".cargo")).unwrap();
+    fs::write(
+        dir.join("Cargo.toml"),
+        "[package]\nname = \"foo\"\nversion = \"0.1.0\"\nedition = \"2021\"\n\n[workspace]\n",
+    )
+    .unwrap();
+    fs::write(
+        dir.join("src").join("lib.rs"),
+        "#[cfg(not(from_config))]\ncompile_error!(\"config rustflags are missing\");\n\
+         #[cfg(not(from_api))]\ncompile_error!(\"added rustflags are missing\");\n",
+    )
+    .unwrap();
+    fs::write(
+        dir.join(".cargo").join("config.toml"),
+        "[build]\nrustflags = [\"--cfg\", \"from_config\", \"-A\", \"unexpected_cfgs\"]\n",
+    )
+    .unwrap();
+    dir
+}
+
+fn check(mut cmd: Command, dir: &Path) {
+    let output = cmd.current_dir(dir).output().unwrap();
+    assert!(
+        output.status.success(),
+        "{:?} failed: {}",
+        cmd,
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
+
+#[test]
+fn config_file_rustflags() {
+    let dir = scratch_package("rustflags-config");
+    let mut cmd = Check::parse_from(["check", "--offline"]).command();
+    cmd.env_remove("CARGO_ENCODED_RUSTFLAGS")
+        .env_remove("RUSTFLAGS")
+        .env_remove("CARGO_BUILD_RUSTFLAGS");
+    RustFlags::new().cfg("from_api").apply(&mut cmd);
+    assert!(cmd.get_envs().all(|(_, value)| value.is_none()));
+    check(cmd, &dir);
+}
+
+#[test]
+fn env_rustflags() {
+    // `RUSTFLAGS` replaces config rustflags, so the added flags go with it
+    let dir = scratch_package("rustflags-env");
+    let mut cmd = Check::parse_from(["check", "--offline"]).command();
+    cmd.env_remove("CARGO_ENCODED_RUSTFLAGS")
+        .env("RUSTFLAGS", "--cfg from_config -A unexpected_cfgs");
+    RustFlags::new().cfg("from_api").apply(&mut cmd);
+    check(cmd, &dir);
+}